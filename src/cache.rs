@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    config::APP_NAME,
+    errors::AppError,
+    providers::{check_horizon, require, Provider},
+    weather::{self, Coordinates, ForecastTime, HistoryRange, UnitType},
+    Result,
+};
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CacheEntry {
+    fetched_at: i64,
+    weather: weather::Weather,
+}
+
+/// Forecast together with the moment it was fetched from the provider.
+#[derive(Debug)]
+pub struct CachedForecast {
+    pub weather: weather::Weather,
+    pub fetched_at: DateTime<Utc>,
+    pub from_cache: bool,
+}
+
+impl CachedForecast {
+    fn from_entry(entry: CacheEntry) -> Result<Self> {
+        let fetched_at =
+            DateTime::from_timestamp(entry.fetched_at, 0).ok_or(AppError::TimeParse(format!(
+                "Failed to parse cached timestamp: {} to DateTime.",
+                entry.fetched_at
+            )))?;
+
+        Ok(CachedForecast {
+            weather: entry.weather,
+            fetched_at,
+            from_cache: true,
+        })
+    }
+
+    /// Human readable age of the forecast, e.g. "5 min ago".
+    pub fn age(&self) -> String {
+        let minutes = (Utc::now() - self.fetched_at).num_minutes().max(0);
+
+        match minutes {
+            0 => "just now".to_string(),
+            1..=59 => format!("{} min ago", minutes),
+            _ => format!("{} h {} min ago", minutes / 60, minutes % 60),
+        }
+    }
+}
+
+/// Caches forecasts of the wrapped provider on disk.
+///
+/// Entries are keyed by the configured provider, coordinates rounded to two decimals, forecast
+/// time and units. Fresh entries are served without a request, stale ones are
+/// used as a fallback when the provider can't be reached. Localities of the
+/// coordinates are kept for good, places don't move.
+pub struct CachedProvider {
+    inner: Box<dyn Provider>,
+    /// Prefix of the file names, see [`weather::Provider::cache_id`]
+    cache_id: String,
+    dir: PathBuf,
+    ttl: Duration,
+    offline: bool,
+    passthrough: bool,
+}
+
+impl CachedProvider {
+    pub fn new(
+        inner: Box<dyn Provider>,
+        cache_id: &str,
+        ttl: Duration,
+        offline: bool,
+    ) -> Result<Self> {
+        Ok(CachedProvider {
+            inner,
+            cache_id: cache_id.to_lowercase().replace(' ', "-"),
+            dir: cache_dir()?,
+            ttl,
+            offline,
+            passthrough: false,
+        })
+    }
+
+    /// Asks the wrapped provider every time, for recording and replaying, which are
+    /// about its replies. Cached data is still read offline.
+    pub fn passthrough(mut self) -> Self {
+        self.ttl = Duration::ZERO;
+        self.passthrough = true;
+        self
+    }
+
+    pub async fn get_cached_forecast(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+        unit: UnitType,
+    ) -> Result<CachedForecast> {
+        check_horizon(self.inner.as_ref(), &time)?;
+        require(
+            self.capabilities().units.contains(&unit),
+            &format!("{} units are", unit),
+        )?;
+        let path = self.entry_path(&coord, &time, &unit);
+
+        if self.offline {
+            return read_json(&path)
+                .ok_or(AppError::NoCachedData)
+                .and_then(CachedForecast::from_entry);
+        }
+
+        let cached = match read_json::<CacheEntry>(&path) {
+            Some(entry) if self.is_fresh(&entry) => return CachedForecast::from_entry(entry),
+            entry => entry,
+        };
+
+        match self.inner.get_forecast(coord, time, unit).await {
+            Ok(weather) => {
+                let fetched_at = Utc::now();
+                let entry = CacheEntry {
+                    fetched_at: fetched_at.timestamp(),
+                    weather,
+                };
+                fs::create_dir_all(&self.dir)?;
+                fs::write(&path, serde_json::to_string(&entry)?)?;
+
+                Ok(CachedForecast {
+                    weather: entry.weather,
+                    fetched_at,
+                    from_cache: false,
+                })
+            }
+            // Serve the last known forecast when the network is down.
+            Err(AppError::ApiRequest(e)) if e.is_connect() || e.is_timeout() => match cached {
+                Some(entry) => CachedForecast::from_entry(entry),
+                None => Err(AppError::ApiRequest(e)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Finds the locality of the coordinates, looked up once per place.
+    async fn get_cached_locality(
+        &self,
+        coord: Coordinates,
+    ) -> Result<Option<weather::GeoLocation>> {
+        if self.passthrough && !self.offline {
+            return self.inner.reverse_geocode(coord).await;
+        }
+
+        let path = self.dir.join(format!(
+            "{}_{:.2}_{:.2}_locality.json",
+            self.cache_id, coord.lat, coord.lon
+        ));
+        if let Some(locality) = read_json(&path) {
+            return Ok(locality);
+        }
+        if self.offline {
+            return Err(AppError::NoCachedData);
+        }
+
+        let locality = self.inner.reverse_geocode(coord).await?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, serde_json::to_string(&locality)?)?;
+
+        Ok(locality)
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        let age = Utc::now().timestamp() - entry.fetched_at;
+        (0..self.ttl.as_secs() as i64).contains(&age)
+    }
+
+    fn entry_path(&self, coord: &Coordinates, time: &ForecastTime, unit: &UnitType) -> PathBuf {
+        let unit = unit.to_string().to_lowercase();
+
+        self.dir.join(format!(
+            "{}_{:.2}_{:.2}_{}_{}.json",
+            self.cache_id, coord.lat, coord.lon, time, unit
+        ))
+    }
+}
+
+#[async_trait]
+impl Provider for CachedProvider {
+    fn capabilities(&self) -> &'static weather::Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn min_interval(&self) -> Duration {
+        self.inner.min_interval()
+    }
+
+    async fn get_forecast(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+        unit: UnitType,
+    ) -> Result<weather::Weather> {
+        Ok(self.get_cached_forecast(coord, time, unit).await?.weather)
+    }
+
+    async fn get_history(
+        &self,
+        coord: Coordinates,
+        range: HistoryRange,
+        unit: UnitType,
+    ) -> Result<weather::Weather> {
+        if range.is_single_day() {
+            require(self.capabilities().hourly_history, "hourly history is")?;
+        } else {
+            require(self.capabilities().daily_history, "daily history is")?;
+        }
+        self.inner.get_history(coord, range, unit).await
+    }
+
+    async fn get_alerts(&self, coord: Coordinates) -> Result<Vec<weather::WeatherAlert>> {
+        require(self.capabilities().alerts, "weather alerts are")?;
+        self.inner.get_alerts(coord).await
+    }
+
+    async fn get_air_quality(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+    ) -> Result<weather::AirForecast> {
+        require(self.capabilities().air_quality.is_some(), "air quality is")?;
+        self.inner.get_air_quality(coord, time).await
+    }
+
+    async fn get_nowcast(&self, coord: Coordinates) -> Result<Vec<weather::MinutePrecip>> {
+        require(
+            self.capabilities().nowcast,
+            "minute by minute forecasts are",
+        )?;
+        self.inner.get_nowcast(coord).await
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<weather::GeoLocation>> {
+        self.inner.geocode(query).await
+    }
+
+    async fn reverse_geocode(&self, coord: Coordinates) -> Result<Option<weather::GeoLocation>> {
+        self.get_cached_locality(coord).await
+    }
+}
+
+/// Removes all the cached forecasts.
+pub fn clear() -> Result<()> {
+    let dir = cache_dir()?;
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+
+    Ok(())
+}
+
+fn cache_dir() -> Result<PathBuf> {
+    ProjectDirs::from("rs", "", APP_NAME)
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .ok_or(AppError::NoCacheDir)
+}
+
+/// Missing or unreadable entries are treated as a cache miss.
+fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
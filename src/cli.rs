@@ -1,9 +1,31 @@
-use clap::{Args, Parser, Subcommand};
+use std::{
+    io::{stdout, IsTerminal},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{Local, NaiveDate};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    value_parser, Args, Parser, Subcommand, ValueEnum,
+};
 
-use crate::{
+use crate::tui;
+
+use weather::{
+    cache, compare,
     config::WeatherConfig,
     errors::AppError,
-    providers::{self, open_weather, weather_api},
+    nowcast,
+    output::{self, ForecastReport, OutputFormat, WaybarStatus, TOOLTIP_HOURS},
+    providers::{
+        self,
+        chain::{ChainForecast, ProviderChain, Skipped},
+    },
+    table::{self, Column, Layout, DEFAULT_COLUMNS},
+    template::{EntryKind, Template},
+    watch,
     weather::*,
     Result,
 };
@@ -18,9 +40,34 @@ pub struct Cli {
 impl Cli {
     pub async fn process(self) -> Result<()> {
         match self.operation {
-            Operation::Configure { provider } => configure_provider(provider),
-            Operation::Places { action } => manage_places(action),
+            Operation::Configure {
+                provider,
+                fallback,
+                name,
+            } => configure_provider(provider, fallback, name),
+            Operation::Providers { action } => describe_providers(action),
+            Operation::Profiles { action } => manage_profiles(action),
+            Operation::Templates { action } => manage_templates(action),
+            Operation::Places { action } => manage_places(action).await,
             Operation::Forecast(args) => get_forecast(args).await,
+            Operation::Compare(args) => compare_providers(args).await,
+            Operation::History(args) => get_history(args).await,
+            Operation::Alerts {
+                location,
+                time_format,
+            } => get_alerts(location, time_format).await,
+            Operation::Tui(args) => show_dashboard(args).await,
+            Operation::Air {
+                location,
+                hours,
+                time_format,
+            } => get_air_quality(location, hours, time_format).await,
+            Operation::Nowcast {
+                location,
+                time_format,
+            } => get_nowcast(location, time_format).await,
+            Operation::Cache { action } => manage_cache(action),
+            Operation::TimeFormat { format } => set_time_format(format),
         }
     }
 }
@@ -31,6 +78,32 @@ enum Operation {
     Configure {
         #[command(subcommand)]
         provider: Provider,
+
+        /// Try this provider only after the already configured ones fail
+        #[arg(long, global = true)]
+        fallback: bool,
+
+        /// Also save the provider as a named profile
+        #[arg(long, global = true)]
+        name: Option<String>,
+    },
+
+    /// Show what the providers can deliver
+    Providers {
+        #[command(subcommand)]
+        action: ProvidersAction,
+    },
+
+    /// Manage named provider profiles
+    Profiles {
+        #[command(subcommand)]
+        action: ProfilesAction,
+    },
+
+    /// Manage named output templates
+    Templates {
+        #[command(subcommand)]
+        action: TemplatesAction,
     },
 
     /// Manage frequently used locations
@@ -41,6 +114,103 @@ enum Operation {
 
     /// Get a weather forecast for the specific location
     Forecast(ForecastArgs),
+
+    /// Compare forecasts of all the configured providers side by side
+    Compare(CompareArgs),
+
+    /// Look up the past weather for the specific location
+    History(HistoryArgs),
+
+    /// List active severe weather alerts for the specific location
+    Alerts {
+        #[command(subcommand)]
+        location: Location,
+
+        /// How to display times, defaults to the configured format
+        #[arg(long, value_enum)]
+        time_format: Option<TimeFormat>,
+    },
+
+    /// Get the air quality now or for the next hours
+    Air {
+        #[command(subcommand)]
+        location: Location,
+
+        /// Forecast the air quality for this many hours instead
+        #[arg(long, value_parser = value_parser!(u64).range(1..))]
+        hours: Option<u64>,
+
+        /// How to display times, defaults to the configured format
+        #[arg(long, value_enum)]
+        time_format: Option<TimeFormat>,
+    },
+
+    /// Show the precipitation expected minute by minute within the next hour
+    Nowcast {
+        #[command(subcommand)]
+        location: Location,
+
+        /// How to display times, defaults to the configured format
+        #[arg(long, value_enum)]
+        time_format: Option<TimeFormat>,
+    },
+
+    /// Browse the forecasts of the saved places in a full-screen dashboard
+    Tui(TuiArgs),
+
+    /// Manage cached forecasts
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Set how times and dates are displayed by default
+    TimeFormat {
+        #[arg(value_enum)]
+        format: TimeFormat,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum ProvidersAction {
+    /// List the horizons, fields and data of every provider, or of this one
+    Info {
+        #[arg(value_parser = PossibleValuesParser::new(providers::AVAILABLE.map(|(id, ..)| id)))]
+        provider: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum ProfilesAction {
+    /// List the saved profiles with masked keys
+    List,
+
+    /// Make the profile the primary provider
+    Use { name: String },
+
+    /// Remove the profile
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum TemplatesAction {
+    /// List the saved templates
+    List,
+
+    /// Save the template under the name, replacing the one saved before
+    Set { name: String, template: String },
+
+    /// Remove the template
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum CacheAction {
+    /// Set for how many seconds cached forecasts stay fresh
+    Ttl { seconds: u64 },
+
+    /// Remove all the cached forecasts
+    Clear,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -49,31 +219,323 @@ struct ForecastArgs {
     #[command(subcommand)]
     location: Location,
 
-    /// Time range to get the weather forecast for
-    #[arg(value_enum, default_value_t = ForecastTime::Now)]
-    time: ForecastTime,
+    #[command(flatten)]
+    range: ForecastRange,
 
     /// Units type
     #[arg(value_enum, default_value_t = UnitType::Metric)]
     unit: UnitType,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// How hourly and daily forecasts are laid out as text
+    #[arg(long, value_enum, default_value_t = Layout::Table)]
+    format: Layout,
+
+    /// Table columns, separated by commas
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = DEFAULT_COLUMNS)]
+    columns: Vec<Column>,
+
+    /// Print a line per entry with this template or the saved one of this name,
+    /// e.g. "{temp:.0}{temp_unit} {condition}"
+    #[arg(long, conflicts_with = "output")]
+    template: Option<String>,
+
+    /// Serve the last cached forecast without making any requests
+    #[arg(long)]
+    offline: bool,
+
+    /// Use only the provider of this profile
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Save the request URLs, without keys, and the raw replies to this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "offline")]
+    record: Option<PathBuf>,
+
+    /// How to display times, defaults to the configured format
+    #[arg(long, value_enum)]
+    time_format: Option<TimeFormat>,
+
+    /// Refresh the forecast every SECONDS and show what changed since the last refresh
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = value_parser!(u64).range(1..),
+        conflicts_with = "offline"
+    )]
+    watch: Option<u64>,
 }
 
-fn configure_provider(prv: Provider) -> Result<()> {
+impl ForecastArgs {
+    /// Status bars show the current weather whatever the range is.
+    fn forecast_time(&self) -> ForecastTime {
+        if self.output.is_status_bar() {
+            ForecastTime::Now
+        } else {
+            self.range.forecast_time()
+        }
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+struct HistoryArgs {
+    /// Location to look the weather up for
+    #[command(subcommand)]
+    location: Location,
+
+    /// Date to look up by the hour, e.g. 2023-11-01
+    date: NaiveDate,
+
+    /// Look up every day until this date instead, both dates included
+    #[arg(long, value_name = "DATE")]
+    to: Option<NaiveDate>,
+
+    /// Units type
+    #[arg(value_enum, default_value_t = UnitType::Metric)]
+    unit: UnitType,
+
+    /// Output format, status bars show the current weather only
+    #[arg(
+        short,
+        long,
+        default_value = "text",
+        value_parser = PossibleValuesParser::new(["text", "json"])
+            .map(|s| OutputFormat::from_str(&s, true).expect("possible values are formats"))
+    )]
+    output: OutputFormat,
+
+    /// How hourly and daily weather is laid out as text
+    #[arg(long, value_enum, default_value_t = Layout::Table)]
+    format: Layout,
+
+    /// Table columns, separated by commas
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = DEFAULT_COLUMNS)]
+    columns: Vec<Column>,
+
+    /// Print a line per entry with this template or the saved one of this name
+    #[arg(long, conflicts_with = "output")]
+    template: Option<String>,
+
+    /// Use only the provider of this profile
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// How to display times, defaults to the configured format
+    #[arg(long, value_enum)]
+    time_format: Option<TimeFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct TuiArgs {
+    /// Units type
+    #[arg(value_enum, default_value_t = UnitType::Metric)]
+    unit: UnitType,
+
+    /// Use only the provider of this profile
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// How to display times, defaults to the configured format
+    #[arg(long, value_enum)]
+    time_format: Option<TimeFormat>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct CompareArgs {
+    /// Location to obtain weather information for
+    #[command(subcommand)]
+    location: Location,
+
+    #[command(flatten)]
+    range: ForecastRange,
+
+    /// Units type
+    #[arg(value_enum, default_value_t = UnitType::Metric)]
+    unit: UnitType,
+
+    /// How to display times, defaults to the configured format
+    #[arg(long, value_enum)]
+    time_format: Option<TimeFormat>,
+}
+
+fn configure_provider(prv: Provider, fallback: bool, profile: Option<String>) -> Result<()> {
     let mut config = WeatherConfig::get()?;
-    config.provider = Some(prv.clone());
+    let name = prv.to_string();
+    if let Some(profile) = profile {
+        config.profiles.insert(profile, prv.clone());
+    }
+    if fallback {
+        config.add_fallback(prv);
+    } else {
+        config.set_primary(prv);
+    }
     config.save()?;
 
-    println!("Provider {} successfully configured!", prv);
+    println!("Provider {} successfully configured!", name);
+    print_chain(&config);
+
+    if config.exposes_keys()? {
+        eprintln!(
+            "Warning: API keys are stored as plain text in {}, which other users can read. \
+            Restrict it with `chmod 600` or pass the key with --key-env, --key-command or --key-file.",
+            WeatherConfig::path()?.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn describe_providers(act: ProvidersAction) -> Result<()> {
+    let config = WeatherConfig::get()?;
+
+    match act {
+        ProvidersAction::Info { provider } => {
+            let listed = providers::AVAILABLE
+                .iter()
+                .filter(|(id, ..)| provider.is_none() || provider.as_deref() == Some(*id));
+
+            for (i, (id, name, capabilities)) in listed.enumerate() {
+                let configured = if config.providers.iter().any(|p| p.to_string() == *name) {
+                    ", configured"
+                } else {
+                    ""
+                };
+                if i > 0 {
+                    println!();
+                }
+                println!("{} ({}){}", name, id, configured);
+                for line in capabilities.to_string().lines() {
+                    println!("  {}", line);
+                }
+            }
+        }
+    }
+
     Ok(())
 }
 
-fn manage_places(act: PlacesAction) -> Result<()> {
+fn manage_profiles(act: ProfilesAction) -> Result<()> {
+    let mut config = WeatherConfig::get()?;
+
+    match act {
+        ProfilesAction::List => {
+            if config.profiles.is_empty() {
+                println!("No profiles saved. Add one with `configure <provider> --name <NAME>`.");
+            }
+
+            for (name, prv) in &config.profiles {
+                let key = match prv {
+                    Provider::OpenWeather(creds) | Provider::WeatherApi(creds) => {
+                        format!(", {}", creds.describe())
+                    }
+                    Provider::OpenMeteo | Provider::Replay(_) => String::new(),
+                };
+                let in_use = if config.providers.first() == Some(prv) {
+                    " (in use)"
+                } else {
+                    ""
+                };
+                println!("{}: {}{}{}", name, prv, key, in_use);
+            }
+        }
+        ProfilesAction::Use { name } => {
+            let prv = config.profile(&name)?.clone();
+            config.set_primary(prv);
+            config.save()?;
+
+            println!("Profile {} is now in use.", name);
+            print_chain(&config);
+        }
+        ProfilesAction::Remove { name } => {
+            config.remove_profile(&name)?;
+            config.save()?;
+
+            println!("Profile {} removed.", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn manage_templates(act: TemplatesAction) -> Result<()> {
+    let mut config = WeatherConfig::get()?;
+
+    match act {
+        TemplatesAction::List => {
+            if config.templates.is_empty() {
+                println!("No templates saved. Add one with `templates set <NAME> <TEMPLATE>`.");
+            }
+
+            for (name, template) in &config.templates {
+                println!("{}: {}", name, template);
+            }
+        }
+        TemplatesAction::Set { name, template } => {
+            Template::parse(&template)?;
+            config.templates.insert(name.clone(), template);
+            config.save()?;
+
+            println!(
+                "Template {} saved, use it with `--template {}`.",
+                name, name
+            );
+        }
+        TemplatesAction::Remove { name } => {
+            config.remove_template(&name)?;
+            config.save()?;
+
+            println!("Template {} removed.", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_chain(config: &WeatherConfig) {
+    println!("Providers are tried in the following order:");
+    for (i, prv) in config.providers.iter().enumerate() {
+        println!("{}. {}", i + 1, prv);
+    }
+}
+
+async fn manage_places(act: PlacesAction) -> Result<()> {
     let mut config = WeatherConfig::get()?;
 
     let places = match act {
         PlacesAction::GetAll => config.places,
-        PlacesAction::Set(place) => {
-            place.coordinates.validate()?;
+        PlacesAction::Set(args) => {
+            let (locality, coordinates) = match (args.query, args.lat, args.lon) {
+                (Some(query), _, _) => {
+                    let found = ProviderChain::new(&config, false, None)?
+                        .locate(&query)
+                        .await?;
+                    println!("Found {}", found);
+                    (Some(found.name), found.coordinates)
+                }
+                (None, Some(lat), Some(lon)) => (None, Coordinates { lat, lon }),
+                _ => unreachable!("clap requires either a query or both coordinates"),
+            };
+            coordinates.validate()?;
+
+            let tag = match (args.tag, locality) {
+                (Some(tag), _) => tag,
+                (None, Some(locality)) => locality,
+                (None, None) => ProviderChain::new(&config, false, None)?
+                    .locality(coordinates.clone())
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|l| l.name)
+                    .ok_or(AppError::MissingPlaceTag)?,
+            };
+
+            let place = Place {
+                tag: PlaceTag { tag },
+                coordinates,
+            };
             config.places.replace(place);
             config.save()?;
 
@@ -97,39 +559,386 @@ fn manage_places(act: PlacesAction) -> Result<()> {
     Ok(())
 }
 
-async fn get_forecast(args: ForecastArgs) -> Result<()> {
-    let config = WeatherConfig::get()?;
-    let prv_type = config.provider.as_ref().ok_or(AppError::EmptyProvider)?;
+fn manage_cache(act: CacheAction) -> Result<()> {
+    match act {
+        CacheAction::Ttl { seconds } => {
+            let mut config = WeatherConfig::get()?;
+            config.cache_ttl = Some(seconds);
+            config.save()?;
 
-    let provider: Box<dyn providers::Provider> = match prv_type {
-        Provider::OpenWeather(creds) => {
-            Box::new(open_weather::OpenWeather::new(creds.key.to_owned())?)
+            println!("Cached forecasts stay fresh for {} seconds.", seconds);
         }
-        Provider::WeatherApi(creds) => {
-            Box::new(weather_api::WeatherApi::new(creds.key.to_owned())?)
+        CacheAction::Clear => {
+            cache::clear()?;
+            println!("Cache cleared.");
         }
+    }
+
+    Ok(())
+}
+
+fn set_time_format(format: TimeFormat) -> Result<()> {
+    let mut config = WeatherConfig::get()?;
+    config.time_format = format;
+    config.save()?;
+
+    println!("Times are displayed as {}.", format.example());
+
+    Ok(())
+}
+
+/// Returns the place name, if any, together with the coordinates of the location.
+async fn resolve_location(
+    config: &WeatherConfig,
+    chain: &ProviderChain,
+    location: Location,
+) -> Result<(Option<String>, Option<Coordinates>)> {
+    Ok(match location {
+        Location::Coordinates(coords) => (None, Some(coords)),
+        Location::Place(tag) => {
+            let coords = config.place_by_tag(&tag).map(|p| p.coordinates);
+            (Some(tag.tag), coords)
+        }
+        Location::City(city) => {
+            let found = chain.locate(&city.name).await?;
+            (Some(found.label()), Some(found.coordinates))
+        }
+    })
+}
+
+async fn get_forecast(args: ForecastArgs) -> Result<()> {
+    let mut config = WeatherConfig::get()?;
+    if let Some(profile) = &args.profile {
+        config.select_profile(profile)?;
+    }
+    let chain = ProviderChain::new(&config, args.offline, args.record.as_deref())?;
+    let template = match &args.template {
+        Some(template) => {
+            let template = Template::parse(config.template(template))?;
+            template.check(EntryKind::from(&args.forecast_time()))?;
+            Some(template)
+        }
+        None => None,
     };
 
-    let coords = match args.location {
-        Location::Coordinates(coords) => Some(coords),
-        Location::Place(tag) => config.place_by_tag(&tag).map(|p| p.coordinates),
+    let (place, coords) = resolve_location(&config, &chain, args.location.clone()).await?;
+    let Some(coords) = coords else {
+        return Ok(());
     };
 
-    if let Some(coords) = coords {
-        let weather = provider.get_forecast(coords, args.time, args.unit).await?;
-        println!("Weather provider: {}", prv_type);
-        match weather {
-            Weather::Current(current) => {
-                println!("{}", current);
+    if let Some(seconds) = args.watch {
+        let place = name_place(&chain, place, &coords).await;
+        return watch_forecast(
+            &args,
+            config,
+            template.as_ref(),
+            &chain,
+            place,
+            coords,
+            seconds,
+        )
+        .await;
+    }
+
+    let result = chain
+        .get_forecast(coords.clone(), args.forecast_time(), args.unit.clone())
+        .await?;
+
+    // Looked up after the forecast, which is shown anyway if this fails.
+    let place = name_place(&chain, place, &coords).await;
+    print_forecast(
+        &args,
+        &config,
+        &chain,
+        template.as_ref(),
+        place.as_deref(),
+        &coords,
+        &result,
+    )
+    .await
+}
+
+/// Status bar outputs are requested for the current weather.
+fn current(weather: &Weather) -> &CurrentWeather {
+    match weather {
+        Weather::Current(current) => current,
+        _ => unreachable!("status bars show the current weather"),
+    }
+}
+
+/// Names the coordinates after their locality unless the location already has a name,
+/// localities are cached so offline runs and status bar refreshes don't look them up again.
+async fn name_place(
+    chain: &ProviderChain,
+    place: Option<String>,
+    coords: &Coordinates,
+) -> Option<String> {
+    match place {
+        None => chain
+            .locality(coords.clone())
+            .await
+            .ok()
+            .flatten()
+            .map(|l| l.label()),
+        place => place,
+    }
+}
+
+async fn print_forecast(
+    args: &ForecastArgs,
+    config: &WeatherConfig,
+    chain: &ProviderChain,
+    template: Option<&Template>,
+    place: Option<&str>,
+    coords: &Coordinates,
+    result: &ChainForecast,
+) -> Result<()> {
+    let time_format = config.time_format(args.time_format);
+
+    match (&args.output, template) {
+        (OutputFormat::Text, Some(template)) => {
+            // Rendered up front, so a bad field doesn't leave half of the output behind.
+            for line in template.lines(&result.forecast.weather, &time_format)? {
+                println!("{}", line);
+            }
+        }
+        (OutputFormat::Text, None) => {
+            print_skipped(&result.skipped);
+            if result.forecast.from_cache {
+                println!("Cached forecast from {}", result.forecast.age());
             }
-            Weather::Today(hours) => {
-                for hour in hours {
-                    println!("{}\n", hour);
+            print_weather(
+                &result.provider,
+                place,
+                &result.forecast.weather,
+                time_format,
+                args.format,
+                &args.columns,
+            );
+        }
+        (OutputFormat::Json, _) => {
+            let report = ForecastReport::new(place, coords, &args.unit, result);
+            println!("{}", report.to_json()?);
+        }
+        (OutputFormat::Waybar, _) => {
+            // The bar still shows the current weather when the tooltip's hours fail.
+            let hours = match chain
+                .get_forecast(
+                    coords.clone(),
+                    ForecastTime::Hours(TOOLTIP_HOURS),
+                    args.unit.clone(),
+                )
+                .await
+                .map(|r| r.forecast.weather)
+            {
+                Ok(Weather::Today(hours)) => hours,
+                _ => Vec::new(),
+            };
+            let status = WaybarStatus::new(
+                &result.provider,
+                place,
+                current(&result.forecast.weather),
+                &hours,
+                &time_format,
+            );
+            println!("{}", status.to_json()?);
+        }
+        (OutputFormat::I3blocks, _) => {
+            print!("{}", output::i3blocks(current(&result.forecast.weather)));
+        }
+        (OutputFormat::Line, _) => {
+            println!("{}", output::summary(current(&result.forecast.weather)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Refreshes the forecast until interrupted, slowing down while the providers fail.
+async fn watch_forecast(
+    args: &ForecastArgs,
+    mut config: WeatherConfig,
+    template: Option<&Template>,
+    chain: &ProviderChain,
+    place: Option<String>,
+    coords: Coordinates,
+    seconds: u64,
+) -> Result<()> {
+    let interval = Duration::from_secs(seconds).max(chain.min_interval());
+    if interval.as_secs() > seconds {
+        eprintln!(
+            "Refreshing every {} seconds to stay within the providers' request limits.",
+            interval.as_secs()
+        );
+    }
+
+    // A forecast cached for longer than the interval would be shown again unchanged.
+    config.cache_ttl = Some(config.cache_ttl().min(interval).as_secs());
+    let chain = ProviderChain::new(&config, args.offline, args.record.as_deref())?;
+    let config = &config;
+    let chain = &chain;
+
+    let time_format = config.time_format(args.time_format);
+    let mut last: Option<Weather> = None;
+    let mut failures = 0;
+
+    loop {
+        let forecast = chain
+            .get_forecast(coords.clone(), args.forecast_time(), args.unit.clone())
+            .await;
+
+        let shown = match forecast {
+            Ok(result) => {
+                if matches!(args.output, OutputFormat::Text) {
+                    // Redraw in place instead of scrolling.
+                    print!("\x1b[2J\x1b[H");
+                }
+                print_forecast(
+                    args,
+                    config,
+                    chain,
+                    template,
+                    place.as_deref(),
+                    &coords,
+                    &result,
+                )
+                .await
+                .map(|()| result)
+            }
+            Err(e) => Err(e),
+        };
+
+        match shown {
+            Ok(result) => {
+                failures = 0;
+
+                if let (OutputFormat::Text, Some(prev)) = (&args.output, &last) {
+                    let changes = watch::changes(prev, &result.forecast.weather, &time_format);
+                    if changes.is_empty() {
+                        println!("No changes since the last refresh.");
+                    } else {
+                        println!("Changes since the last refresh:");
+                        for change in changes {
+                            println!("  {}", change);
+                        }
+                    }
                 }
+                last = Some(result.forecast.weather);
+            }
+            // A dropped connection or an odd reply may clear up, so keep watching.
+            Err(e) => {
+                failures += 1;
+                eprint!("Refresh failed: {}", e);
+            }
+        }
+
+        let delay = watch::next_delay(interval, failures);
+        if matches!(args.output, OutputFormat::Text) {
+            let next = Local::now().fixed_offset() + delay;
+            println!(
+                "Next refresh at {}, press Ctrl+C to stop.",
+                time_format.time(&next)
+            );
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn compare_providers(args: CompareArgs) -> Result<()> {
+    let config = WeatherConfig::get()?;
+    let chain = ProviderChain::new(&config, false, None)?;
+
+    if let (_, Some(coords)) = resolve_location(&config, &chain, args.location).await? {
+        let results = chain
+            .get_all(coords, args.range.forecast_time(), args.unit.clone())
+            .await;
+        let time_format = config.time_format(args.time_format);
+        print!("{}", compare::render(&args.unit, &time_format, &results));
+    }
+
+    Ok(())
+}
+
+async fn get_history(args: HistoryArgs) -> Result<()> {
+    let range = HistoryRange::new(args.date, args.to, Local::now().date_naive())?;
+    let mut config = WeatherConfig::get()?;
+    if let Some(profile) = &args.profile {
+        config.select_profile(profile)?;
+    }
+    let chain = ProviderChain::new(&config, false, None)?;
+    let template = match &args.template {
+        Some(template) => {
+            let template = Template::parse(config.template(template))?;
+            // A single date is shown by the hour, a range by the day.
+            template.check(match range.is_single_day() {
+                true => EntryKind::Hour,
+                false => EntryKind::Day,
+            })?;
+            Some(template)
+        }
+        None => None,
+    };
+
+    let (place, coords) = resolve_location(&config, &chain, args.location).await?;
+    let Some(coords) = coords else {
+        return Ok(());
+    };
+
+    let result = chain
+        .get_history(coords.clone(), range, args.unit.clone())
+        .await?;
+    let place = name_place(&chain, place, &coords).await;
+    let time_format = config.time_format(args.time_format);
+
+    match (&args.output, template) {
+        (OutputFormat::Json, _) => {
+            let report = ForecastReport::new(place.as_deref(), &coords, &args.unit, &result);
+            println!("{}", report.to_json()?);
+        }
+        (_, Some(template)) => {
+            for line in template.lines(&result.forecast.weather, &time_format)? {
+                println!("{}", line);
             }
-            Weather::Daily(days) => {
-                for day in days {
-                    println!("{}\n", day);
+        }
+        (_, None) => {
+            print_skipped(&result.skipped);
+            print_weather(
+                &result.provider,
+                place.as_deref(),
+                &result.forecast.weather,
+                time_format,
+                args.format,
+                &args.columns,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_air_quality(
+    location: Location,
+    hours: Option<u64>,
+    time_format: Option<TimeFormat>,
+) -> Result<()> {
+    let config = WeatherConfig::get()?;
+    let chain = ProviderChain::new(&config, false, None)?;
+
+    if let (place, Some(coords)) = resolve_location(&config, &chain, location).await? {
+        let time = hours.map_or(ForecastTime::Now, |n| ForecastTime::Hours(n as usize));
+        let result = chain.get_air_quality(coords, time).await?;
+        print_skipped(&result.skipped);
+        println!("Air quality provider: {}", result.provider);
+        if let Some(place) = place {
+            println!("Location: {}", place);
+        }
+
+        let time_format = config.time_format(time_format);
+        match result.air {
+            AirForecast::Current(air) => println!("{}", air.formatted(time_format)),
+            AirForecast::Hourly(hours) => {
+                for air in hours {
+                    println!("{}\n", air.formatted(time_format));
                 }
             }
         }
@@ -137,3 +946,125 @@ async fn get_forecast(args: ForecastArgs) -> Result<()> {
 
     Ok(())
 }
+
+async fn get_nowcast(location: Location, time_format: Option<TimeFormat>) -> Result<()> {
+    let config = WeatherConfig::get()?;
+    let chain = ProviderChain::new(&config, false, None)?;
+
+    if let (place, Some(coords)) = resolve_location(&config, &chain, location).await? {
+        let result = chain.get_nowcast(coords).await?;
+        print_skipped(&result.skipped);
+        println!("Nowcast provider: {}", result.provider);
+        if let Some(place) = place {
+            println!("Location: {}", place);
+        }
+
+        let time_format = config.time_format(time_format);
+        if let (Some(first), Some(last)) = (result.minutes.first(), result.minutes.last()) {
+            println!(
+                "{} ▕{}▏ {}",
+                time_format.time(&first.time),
+                nowcast::sparkline(&result.minutes),
+                time_format.time(&last.time)
+            );
+        }
+        println!("{}", nowcast::summary(&result.minutes));
+    }
+
+    Ok(())
+}
+
+async fn show_dashboard(args: TuiArgs) -> Result<()> {
+    let mut config = WeatherConfig::get()?;
+    if let Some(profile) = &args.profile {
+        config.select_profile(profile)?;
+    }
+    let chain = ProviderChain::new(&config, false, None)?;
+
+    if config.places.is_empty() {
+        println!("No places saved. Add one with `places set`.");
+        return Ok(());
+    }
+
+    let time_format = config.time_format(args.time_format);
+    let places = config.places.into_iter().collect();
+    tui::run(Arc::new(chain), places, args.unit, time_format).await
+}
+
+async fn get_alerts(location: Location, time_format: Option<TimeFormat>) -> Result<()> {
+    let config = WeatherConfig::get()?;
+    let chain = ProviderChain::new(&config, false, None)?;
+
+    if let (_, Some(coords)) = resolve_location(&config, &chain, location).await? {
+        let result = chain.get_alerts(coords).await?;
+        print_skipped(&result.skipped);
+        println!("Weather provider: {}", result.provider);
+
+        if result.alerts.is_empty() {
+            println!("No active weather alerts.");
+        }
+
+        let time_format = config.time_format(time_format);
+        for alert in result.alerts {
+            println!("{}\n", alert.formatted(time_format));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_skipped(skipped: &[Skipped]) {
+    for s in skipped {
+        print!("Skipped {}: {}", s.provider, s.error);
+    }
+}
+
+fn print_weather(
+    provider: &str,
+    place: Option<&str>,
+    weather: &Weather,
+    time_format: TimeFormat,
+    layout: Layout,
+    columns: &[Column],
+) {
+    println!("Weather provider: {}", provider);
+    if let Some(place) = place {
+        println!("Location: {}", place);
+    }
+    match (weather, layout) {
+        (Weather::Current(current), _) => {
+            println!("{}", current.formatted(time_format));
+        }
+        (Weather::Today(hours), Layout::Table) => {
+            print!(
+                "{}",
+                table::hours(hours, columns, &time_format, terminal_width())
+            );
+        }
+        (Weather::Daily(days), Layout::Table) => {
+            print!(
+                "{}",
+                table::days(days, columns, &time_format, terminal_width())
+            );
+        }
+        (Weather::Today(hours), Layout::Blocks) => {
+            for hour in hours {
+                println!("{}\n", hour.formatted(time_format));
+            }
+        }
+        (Weather::Daily(days), Layout::Blocks) => {
+            for day in days {
+                println!("{}\n", day.formatted(time_format));
+            }
+        }
+    }
+}
+
+/// Width of the terminal, tables aren't cut when the output goes elsewhere.
+fn terminal_width() -> usize {
+    if !stdout().is_terminal() {
+        return usize::MAX;
+    }
+
+    crossterm::terminal::size().map_or(80, |(columns, _)| columns as usize)
+}
@@ -0,0 +1,295 @@
+use crate::{
+    cache::CachedForecast,
+    weather::{
+        cardinal_dir_from, CurrentWeather, DailyWeather, HourWeather, TimeFormat, UnitType, Weather,
+    },
+    Result,
+};
+
+// Temperature spreads, in °C and °F, that are worth pointing out.
+const TEMP_SPREAD_METRIC: f32 = 3.0;
+const TEMP_SPREAD_IMPERIAL: f32 = 5.4;
+
+// Precipitation, in mm and inches, below which there is treated as none at all.
+const PRECIP_THRESHOLD_METRIC: f32 = 0.1;
+const PRECIP_THRESHOLD_IMPERIAL: f32 = 0.004;
+
+struct Row {
+    label: String,
+    values: Vec<String>,
+    note: Option<String>,
+}
+
+/// Renders forecasts of several providers side by side in aligned columns.
+pub fn render(
+    unit: &UnitType,
+    time_format: &TimeFormat,
+    results: &[(String, Result<CachedForecast>)],
+) -> String {
+    let weathers = results
+        .iter()
+        .map(|(_, r)| r.as_ref().ok().map(|f| &f.weather))
+        .collect::<Vec<Option<&Weather>>>();
+
+    let mut out = String::new();
+    let header = Row {
+        label: String::new(),
+        values: results.iter().map(|(name, _)| name.to_owned()).collect(),
+        note: None,
+    };
+
+    match weathers.iter().flatten().next() {
+        Some(Weather::Current(_)) => {
+            let items = weathers
+                .iter()
+                .map(|w| match w {
+                    Some(Weather::Current(c)) => Some(c),
+                    _ => None,
+                })
+                .collect::<Vec<Option<&CurrentWeather>>>();
+
+            push_section(
+                &mut out,
+                "Current Weather:",
+                &header,
+                current_rows(&items, unit),
+            );
+        }
+        Some(Weather::Today(_)) => {
+            let hours = weathers
+                .iter()
+                .map(|w| match w {
+                    Some(Weather::Today(hours)) => hours.as_slice(),
+                    _ => &[],
+                })
+                .collect::<Vec<&[HourWeather]>>();
+            let mut times = hours
+                .iter()
+                .flat_map(|h| h.iter().map(|h| h.time))
+                .collect::<Vec<_>>();
+            times.sort();
+            times.dedup();
+
+            for time in times {
+                // Providers without this hour are left blank.
+                let items = hours
+                    .iter()
+                    .map(|h| h.iter().find(|h| h.time == time))
+                    .collect::<Vec<Option<&HourWeather>>>();
+
+                push_section(
+                    &mut out,
+                    &format!("Time: {}", time_format.datetime(&time)),
+                    &header,
+                    hour_rows(&items, unit),
+                );
+            }
+        }
+        Some(Weather::Daily(_)) => {
+            let days = weathers
+                .iter()
+                .map(|w| match w {
+                    Some(Weather::Daily(days)) => days.as_slice(),
+                    _ => &[],
+                })
+                .collect::<Vec<&[DailyWeather]>>();
+            let mut dates = days
+                .iter()
+                .flat_map(|d| d.iter().map(|d| d.date))
+                .collect::<Vec<_>>();
+            dates.sort();
+            dates.dedup();
+
+            for date in dates {
+                let items = days
+                    .iter()
+                    .map(|d| d.iter().find(|d| d.date == date))
+                    .collect::<Vec<Option<&DailyWeather>>>();
+
+                push_section(
+                    &mut out,
+                    &format!("Daily Weather for {}:", time_format.date(&date)),
+                    &header,
+                    day_rows(&items, unit),
+                );
+            }
+        }
+        None => {}
+    }
+
+    for (name, result) in results {
+        if let Err(e) = result {
+            out.push_str(&format!("{} failed: {}", name, e));
+        }
+    }
+
+    out
+}
+
+fn current_rows(items: &[Option<&CurrentWeather>], unit: &UnitType) -> Vec<Row> {
+    let (_, speed_unit, vis_unit, _) = unit.symbols();
+
+    vec![
+        temp_row("Temperature", pick(items, |c| Some(c.temp)), unit),
+        temp_row("Feels Like", pick(items, |c| Some(c.feels_like)), unit),
+        number_row("Humidity", pick(items, |c| Some(c.humidity)), "%"),
+        number_row("Pressure", pick(items, |c| Some(c.pressure)), " hPa"),
+        number_row(
+            "Wind Speed",
+            pick(items, |c| Some(c.wind_speed)),
+            &format!(" {}", speed_unit),
+        ),
+        text_row(
+            "Wind Direction",
+            pick(items, |c| Some(cardinal_dir_from(c.wind_deg))),
+        ),
+        number_row("Clouds", pick(items, |c| Some(c.clouds)), "%"),
+        number_row(
+            "Visibility",
+            pick(items, |c| Some(c.visibility)),
+            &format!(" {}", vis_unit),
+        ),
+        number_row("UV Index", pick(items, |c| Some(c.uvi)), ""),
+        precip_row(pick(items, |c| c.precip), unit),
+        text_row("Condition", pick(items, |c| Some(c.condition.to_owned()))),
+    ]
+}
+
+fn hour_rows(items: &[Option<&HourWeather>], unit: &UnitType) -> Vec<Row> {
+    let (_, speed_unit, _, _) = unit.symbols();
+
+    vec![
+        temp_row("Temperature", pick(items, |h| Some(h.temp)), unit),
+        temp_row("Feels Like", pick(items, |h| Some(h.feels_like)), unit),
+        number_row("Humidity", pick(items, |h| Some(h.humidity)), "%"),
+        number_row(
+            "Wind Speed",
+            pick(items, |h| Some(h.wind_speed)),
+            &format!(" {}", speed_unit),
+        ),
+        text_row(
+            "Wind Direction",
+            pick(items, |h| Some(cardinal_dir_from(h.wind_deg))),
+        ),
+        number_row("Clouds", pick(items, |h| Some(h.clouds)), "%"),
+        precip_row(pick(items, |h| h.precip), unit),
+        text_row("Condition", pick(items, |h| Some(h.condition.to_owned()))),
+    ]
+}
+
+fn day_rows(items: &[Option<&DailyWeather>], unit: &UnitType) -> Vec<Row> {
+    let (_, speed_unit, _, _) = unit.symbols();
+
+    vec![
+        temp_row("Min Temp", pick(items, |d| Some(d.min_temp)), unit),
+        temp_row("Max Temp", pick(items, |d| Some(d.max_temp)), unit),
+        number_row("Humidity", pick(items, |d| Some(d.humidity)), "%"),
+        number_row(
+            "Wind Speed",
+            pick(items, |d| Some(d.wind_speed)),
+            &format!(" {}", speed_unit),
+        ),
+        number_row("UV Index", pick(items, |d| Some(d.uvi)), ""),
+        precip_row(pick(items, |d| d.precip), unit),
+        text_row("Condition", pick(items, |d| Some(d.condition.to_owned()))),
+    ]
+}
+
+fn pick<T, V>(items: &[Option<&T>], field: impl Fn(&T) -> Option<V>) -> Vec<Option<V>> {
+    items.iter().map(|item| item.and_then(&field)).collect()
+}
+
+fn number_row(label: &str, values: Vec<Option<f32>>, suffix: &str) -> Row {
+    Row {
+        label: label.to_string(),
+        values: values
+            .iter()
+            .map(|v| v.map_or("-".to_string(), |v| format!("{:.2}{}", v, suffix)))
+            .collect(),
+        note: None,
+    }
+}
+
+fn text_row(label: &str, values: Vec<Option<String>>) -> Row {
+    Row {
+        label: label.to_string(),
+        values: values
+            .into_iter()
+            .map(|v| v.unwrap_or("-".to_string()))
+            .collect(),
+        note: None,
+    }
+}
+
+fn temp_row(label: &str, values: Vec<Option<f32>>, unit: &UnitType) -> Row {
+    let (temp_unit, _, _, _) = unit.symbols();
+    let threshold = match unit {
+        UnitType::Metric => TEMP_SPREAD_METRIC,
+        UnitType::Imperial => TEMP_SPREAD_IMPERIAL,
+    };
+
+    let known = values.iter().flatten();
+    let min = known.clone().fold(f32::INFINITY, |a, &b| a.min(b));
+    let max = known.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+
+    let mut row = number_row(label, values, &format!(" {}", temp_unit));
+    if max - min > threshold {
+        row.note = Some(format!("spread {:.2} {}", max - min, temp_unit));
+    }
+
+    row
+}
+
+fn precip_row(values: Vec<Option<f32>>, unit: &UnitType) -> Row {
+    let (_, _, _, precip_unit) = unit.symbols();
+    let threshold = precip_threshold(unit);
+
+    let known = values.iter().flatten();
+    let wet = known.clone().any(|&v| v >= threshold);
+    let dry = known.clone().any(|&v| v < threshold);
+
+    let mut row = number_row("Precipitation", values, &format!(" {}", precip_unit));
+    if wet && dry {
+        row.note = Some("precipitation disagreement".to_string());
+    }
+
+    row
+}
+
+/// Smallest amount of precipitation, in the unit's symbol, that counts as any.
+pub(crate) fn precip_threshold(unit: &UnitType) -> f32 {
+    match unit {
+        UnitType::Metric => PRECIP_THRESHOLD_METRIC,
+        UnitType::Imperial => PRECIP_THRESHOLD_IMPERIAL,
+    }
+}
+
+fn push_section(out: &mut String, title: &str, header: &Row, rows: Vec<Row>) {
+    let label_width = rows
+        .iter()
+        .map(|r| r.label.chars().count())
+        .max()
+        .unwrap_or_default();
+    let widths = (0..header.values.len())
+        .map(|i| {
+            rows.iter()
+                .chain(std::iter::once(header))
+                .map(|r| r.values[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<usize>>();
+
+    out.push_str(&format!("{}\n", title));
+    for row in std::iter::once(header).chain(rows.iter()) {
+        out.push_str(&format!("{:<width$}", row.label, width = label_width));
+        for (value, width) in row.values.iter().zip(&widths) {
+            out.push_str(&format!("  {:<width$}", value, width = width));
+        }
+        if let Some(note) = &row.note {
+            out.push_str(&format!("  ! {}", note));
+        }
+        out.push('\n');
+    }
+    out.push('\n');
+}
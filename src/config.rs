@@ -1,30 +1,138 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
 
 use crate::{
-    weather::{Place, PlaceTag, Provider},
+    errors::AppError,
+    secret,
+    weather::{Place, PlaceTag, Provider, TimeFormat},
     Result,
 };
-use confy::{load, store};
+use confy::{get_configuration_file_path, load, store};
 use serde_derive::{Deserialize, Serialize};
 
-const APP_NAME: &str = "weather";
+pub(crate) const APP_NAME: &str = "weather";
 const CONFIG_NAME: &str = "weather_config";
+const DEFAULT_CACHE_TTL: u64 = 600;
 
+/// Configuration stored in the user's configuration directory.
 #[derive(Deserialize, Serialize, Default, Debug)]
 pub struct WeatherConfig {
-    pub provider: Option<Provider>,
+    /// Providers in the order they are tried, the first one is the primary
+    #[serde(default)]
+    pub providers: Vec<Provider>,
+    /// Single provider stored by older versions, moved to `providers` on load
+    #[serde(default, skip_serializing)]
+    provider: Option<Provider>,
+    /// Named providers to switch between, e.g. a personal and a team key
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Provider>,
     pub places: HashSet<Place>,
+    /// How long cached forecasts stay fresh, in seconds
+    #[serde(default)]
+    pub cache_ttl: Option<u64>,
+    /// How times and dates are displayed unless overridden with `--time-format`
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    /// Named output templates for `--template`
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
 }
 
 impl WeatherConfig {
+    /// Loads the stored configuration, an empty one if nothing was saved yet.
     pub fn get() -> Result<Self> {
-        Ok(load(APP_NAME, CONFIG_NAME)?)
+        let mut config: Self = load(APP_NAME, CONFIG_NAME)?;
+        if let Some(prv) = config.provider.take() {
+            config.set_primary(prv);
+        }
+
+        Ok(config)
     }
 
     pub fn save(&self) -> Result<()> {
         Ok(store(APP_NAME, CONFIG_NAME, self)?)
     }
 
+    pub fn path() -> Result<PathBuf> {
+        Ok(get_configuration_file_path(APP_NAME, CONFIG_NAME)?)
+    }
+
+    /// Whether a plain text API key is stored where everyone on the machine can read it.
+    pub fn exposes_keys(&self) -> Result<bool> {
+        let plaintext = self
+            .providers
+            .iter()
+            .chain(self.profiles.values())
+            .any(|p| match p {
+                Provider::OpenWeather(creds) | Provider::WeatherApi(creds) => creds.key.is_some(),
+                Provider::OpenMeteo | Provider::Replay(_) => false,
+            });
+
+        Ok(plaintext && secret::is_world_readable(&Self::path()?)?)
+    }
+
+    /// Makes the provider the first one to be tried, moving it there if it is
+    /// already configured with the same credentials.
+    pub fn set_primary(&mut self, prv: Provider) {
+        self.providers.retain(|p| p != &prv);
+        self.providers.insert(0, prv);
+    }
+
+    /// Appends the provider to the end of the chain, moving it there if it is
+    /// already configured with the same credentials.
+    pub fn add_fallback(&mut self, prv: Provider) {
+        self.providers.retain(|p| p != &prv);
+        self.providers.push(prv);
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Provider> {
+        self.profiles
+            .get(name)
+            .ok_or(AppError::UnknownProfile(name.to_string()))
+    }
+
+    /// Queries only the profile's provider for the rest of the run, nothing is saved.
+    pub fn select_profile(&mut self, name: &str) -> Result<()> {
+        self.providers = vec![self.profile(name)?.clone()];
+        Ok(())
+    }
+
+    /// Forgets the profile, together with its copy in the chain.
+    pub fn remove_profile(&mut self, name: &str) -> Result<()> {
+        let prv = self
+            .profiles
+            .remove(name)
+            .ok_or(AppError::UnknownProfile(name.to_string()))?;
+        self.providers.retain(|p| *p != prv);
+        Ok(())
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL))
+    }
+
+    /// Format from the command line if given, the configured one otherwise.
+    pub fn time_format(&self, time_format: Option<TimeFormat>) -> TimeFormat {
+        time_format.unwrap_or(self.time_format)
+    }
+
+    /// Saved template of this name, the argument itself is the template otherwise.
+    pub fn template<'a>(&'a self, template: &'a str) -> &'a str {
+        self.templates
+            .get(template)
+            .map_or(template, |t| t.as_str())
+    }
+
+    pub fn remove_template(&mut self, name: &str) -> Result<()> {
+        self.templates
+            .remove(name)
+            .map(|_| ())
+            .ok_or(AppError::UnknownTemplate(name.to_string()))
+    }
+
     pub fn place_by_tag(&self, tag: &PlaceTag) -> Option<Place> {
         self.places.iter().find(|p| p.tag == *tag).cloned()
     }
@@ -1,6 +1,8 @@
+use crate::{providers::chain::Skipped, weather::GeoLocation};
 use confy::ConfyError;
 use reqwest::Error as ReqwestError;
-use std::fmt;
+use serde_json::Error as JsonError;
+use std::{fmt, io::Error as IoError};
 use url::ParseError as UrlError;
 
 #[derive(Debug)]
@@ -8,9 +10,30 @@ pub enum AppError {
     ConfigSetup(ConfyError),
     ApiRequest(ReqwestError),
     UrlParse(UrlError),
+    Serialization(JsonError),
     TimeParse(String),
     Coordinates(CoordinatesError),
     EmptyProvider,
+    UnknownProfile(String),
+    UnknownTemplate(String),
+    Template(String),
+    DateRange(String),
+    Secret(String),
+    Unsupported(String),
+    Io(IoError),
+    NoCacheDir,
+    NoCachedData,
+    NoRecording(String),
+    AllProvidersFailed(Vec<Skipped>),
+    LocationNotFound(String),
+    AmbiguousLocation(String, Vec<GeoLocation>),
+    MissingPlaceTag,
+    HorizonExceeded(String),
+    IncompleteHistory(String),
+    InvalidKey(String),
+    QuotaExceeded(String),
+    ProviderSchemaChanged(String),
+    ProviderResponse(u16, String),
 }
 
 #[derive(Debug)]
@@ -25,6 +48,7 @@ impl fmt::Display for AppError {
             AppError::ConfigSetup(e) => writeln!(f, "Config setup error: {}", e),
             AppError::ApiRequest(e) => writeln!(f, "Api request error: {}", e),
             AppError::UrlParse(e) => writeln!(f, "Url parse error: {}", e),
+            AppError::Serialization(e) => writeln!(f, "Serialization error: {}", e),
             AppError::TimeParse(e) => writeln!(f, "Failed to parse time: {}", e),
             AppError::Coordinates(e) => {
                 writeln!(f, "Provided coordinate is incorrent, error: {}", e)
@@ -33,6 +57,130 @@ impl fmt::Display for AppError {
                 f,
                 "Weather provider is not configured! Call --help to find instructions."
             ),
+            AppError::UnknownProfile(name) => writeln!(
+                f,
+                "Profile \"{}\" is not configured. Call `profiles list` to see the available ones.",
+                name
+            ),
+            AppError::UnknownTemplate(name) => writeln!(
+                f,
+                "Template \"{}\" is not saved. Call `templates list` to see the available ones.",
+                name
+            ),
+            AppError::Template(e) => writeln!(f, "Invalid template: {}", e),
+            AppError::DateRange(e) => writeln!(f, "Invalid date range: {}", e),
+            AppError::Secret(e) => writeln!(f, "Failed to read the API key: {}", e),
+            AppError::Unsupported(e) => {
+                writeln!(f, "Not supported by the configured provider: {}", e)
+            }
+            AppError::Io(e) => writeln!(f, "IO error: {}", e),
+            AppError::NoCacheDir => writeln!(f, "Failed to locate the cache directory."),
+            AppError::NoCachedData => writeln!(
+                f,
+                "No cached forecast is available for this request. Run it once while online."
+            ),
+            AppError::NoRecording(e) => writeln!(f, "Failed to replay: {}", e),
+            AppError::LocationNotFound(e) => writeln!(f, "Location not found: {}", e),
+            AppError::AmbiguousLocation(query, candidates) => {
+                writeln!(
+                    f,
+                    "Several places match \"{}\", please add a country code or region:",
+                    query
+                )?;
+                for candidate in candidates {
+                    writeln!(f, "  {}", candidate)?;
+                }
+                Ok(())
+            }
+            AppError::MissingPlaceTag => writeln!(
+                f,
+                "Failed to name the place after its locality, please provide --tag."
+            ),
+            AppError::HorizonExceeded(e) => writeln!(f, "Forecast is too far ahead: {}", e),
+            AppError::IncompleteHistory(e) => writeln!(f, "Weather history is incomplete: {}", e),
+            AppError::InvalidKey(e) => writeln!(
+                f,
+                "API key was rejected: {}. Configure the provider again with a valid key.",
+                e
+            ),
+            AppError::QuotaExceeded(e) => {
+                writeln!(f, "Request quota of the provider is exhausted: {}", e)
+            }
+            AppError::ProviderSchemaChanged(e) => writeln!(
+                f,
+                "Unexpected response from the provider, its API may have changed: {}",
+                e
+            ),
+            AppError::ProviderResponse(status, e) => {
+                writeln!(f, "Provider responded with status {}: {}", status, e)
+            }
+            AppError::AllProvidersFailed(skipped) => {
+                writeln!(f, "All configured providers failed:")?;
+                for s in skipped {
+                    write!(f, "{}: {}", s.provider, s.error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl AppError {
+    /// Whether the error is caused by the provider itself, so another one may be tried.
+    pub fn is_provider_failure(&self) -> bool {
+        matches!(
+            self,
+            AppError::ApiRequest(_)
+                | AppError::TimeParse(_)
+                | AppError::Secret(_)
+                | AppError::Unsupported(_)
+                | AppError::HorizonExceeded(_)
+                | AppError::IncompleteHistory(_)
+                | AppError::NoCachedData
+                | AppError::NoRecording(_)
+                | AppError::LocationNotFound(_)
+                | AppError::InvalidKey(_)
+                | AppError::QuotaExceeded(_)
+                | AppError::ProviderSchemaChanged(_)
+                | AppError::ProviderResponse(..)
+        )
+    }
+
+    /// Process exit code, distinct for each kind of failure a scheduled job may act upon.
+    ///
+    /// When every provider of the chain failed the same way, its code is used.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::ConfigSetup(_)
+            | AppError::EmptyProvider
+            | AppError::UnknownProfile(_)
+            | AppError::UnknownTemplate(_)
+            | AppError::Secret(_) => 3,
+            AppError::Template(_) | AppError::DateRange(_) => 2,
+            AppError::InvalidKey(_) => 4,
+            AppError::QuotaExceeded(_) => 5,
+            AppError::LocationNotFound(_)
+            | AppError::AmbiguousLocation(..)
+            | AppError::Coordinates(_)
+            | AppError::MissingPlaceTag => 6,
+            AppError::ProviderSchemaChanged(_) => 7,
+            AppError::ApiRequest(_)
+            | AppError::ProviderResponse(..)
+            | AppError::IncompleteHistory(_) => 8,
+            AppError::Unsupported(_) | AppError::HorizonExceeded(_) => 9,
+            AppError::NoCachedData | AppError::NoRecording(_) => 10,
+            AppError::AllProvidersFailed(skipped) => {
+                let mut codes = skipped.iter().map(|s| s.error.exit_code());
+                match codes.next() {
+                    Some(code) if codes.all(|c| c == code) => code,
+                    _ => 8,
+                }
+            }
+            AppError::UrlParse(_)
+            | AppError::Serialization(_)
+            | AppError::TimeParse(_)
+            | AppError::Io(_)
+            | AppError::NoCacheDir => 1,
         }
     }
 }
@@ -60,7 +208,8 @@ impl From<ConfyError> for AppError {
 
 impl From<ReqwestError> for AppError {
     fn from(value: ReqwestError) -> Self {
-        AppError::ApiRequest(value)
+        // The URL carries the API key in its query.
+        AppError::ApiRequest(value.without_url())
     }
 }
 
@@ -69,3 +218,15 @@ impl From<UrlError> for AppError {
         AppError::UrlParse(value)
     }
 }
+
+impl From<JsonError> for AppError {
+    fn from(value: JsonError) -> Self {
+        AppError::Serialization(value)
+    }
+}
+
+impl From<IoError> for AppError {
+    fn from(value: IoError) -> Self {
+        AppError::Io(value)
+    }
+}
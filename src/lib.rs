@@ -0,0 +1,61 @@
+//! Weather forecasts from several providers behind a single interface.
+//!
+//! The `weather` binary is a thin command line layer on top of this crate, other
+//! services can embed the same forecast logic. Providers are configured in
+//! [`WeatherConfig`], which is stored in the user's configuration directory, and
+//! are queried through a [`ProviderChain`] that tries them in the configured order
+//! and caches the answers.
+//!
+//! ```no_run
+//! use weather::{
+//!     weather::{Coordinates, ForecastTime, UnitType},
+//!     ProviderChain, WeatherConfig,
+//! };
+//!
+//! # async fn run() -> weather::Result<()> {
+//! let config = WeatherConfig::get()?;
+//! let chain = ProviderChain::new(&config, false, None)?;
+//!
+//! let coord = Coordinates { lat: 49.84, lon: 24.03 };
+//! let result = chain
+//!     .get_forecast(coord, ForecastTime::Hours(12), UnitType::Metric)
+//!     .await?;
+//! println!("{} answered: {:?}", result.provider, result.forecast.weather);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A single provider can be built with [`providers::from_config`] or created
+//! directly, e.g. [`providers::open_meteo::OpenMeteo::new`], and used through the
+//! [`Provider`] trait.
+
+/// On-disk cache of forecasts wrapped around a provider.
+pub mod cache;
+/// Side-by-side rendering of forecasts from several providers.
+pub mod compare;
+/// Persistent configuration of providers, places and preferences.
+pub mod config;
+/// Errors returned by the providers and the configuration.
+pub mod errors;
+/// Sparkline and plain-language summary of the minute by minute precipitation.
+pub mod nowcast;
+/// Machine readable forecast documents.
+pub mod output;
+/// Weather provider clients and the chain trying them in order.
+pub mod providers;
+/// API keys kept outside of the configuration file.
+pub mod secret;
+/// Compact rendering of forecasts with one row per hour or day.
+pub mod table;
+/// User-defined one-line output formats.
+pub mod template;
+/// Changes between refreshed forecasts and the pace of refreshing.
+pub mod watch;
+/// Provider independent forecast model.
+pub mod weather;
+
+pub use config::WeatherConfig;
+pub use errors::AppError;
+pub use providers::{chain::ProviderChain, Provider};
+
+pub type Result<T> = std::result::Result<T, errors::AppError>;
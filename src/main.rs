@@ -1,20 +1,19 @@
+use std::process::ExitCode;
+
 use crate::cli::Cli;
 
 use clap::Parser;
 
-pub mod cli;
-pub mod config;
-pub mod errors;
-pub mod providers;
-pub mod weather;
-
-pub type Result<T> = std::result::Result<T, errors::AppError>;
+mod cli;
+mod tui;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    if let Err(e) = Cli::parse().process().await {
-        println!("{e}");
+async fn main() -> ExitCode {
+    match Cli::parse().process().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(e.exit_code())
+        }
     }
-
-    Ok(())
 }
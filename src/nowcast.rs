@@ -0,0 +1,71 @@
+use crate::weather::MinutePrecip;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Intensity in mm/h from which a minute counts as rainy, lower readings are radar noise.
+const WET_INTENSITY: f32 = 0.1;
+
+/// Intensity in mm/h of the tallest bar unless the peak is higher, so drizzle stays low.
+const FULL_BAR: f32 = 4.0;
+
+/// Character per minute, blank while it's dry and taller the heavier it rains.
+pub fn sparkline(minutes: &[MinutePrecip]) -> String {
+    let peak = minutes.iter().map(|m| m.precip).fold(FULL_BAR, f32::max);
+
+    minutes
+        .iter()
+        .map(|m| {
+            if is_wet(m) {
+                let level = (m.precip / peak * (BARS.len() - 1) as f32).round() as usize;
+                BARS[level.min(BARS.len() - 1)]
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
+
+/// When the rain starts and stops, e.g. "Rain starting in 12 min, stopping in 35 min".
+pub fn summary(minutes: &[MinutePrecip]) -> String {
+    let Some(first) = minutes.first() else {
+        return "No minute by minute forecast is available".to_string();
+    };
+    let after = |m: &MinutePrecip| (m.time - first.time).num_minutes();
+    let span = minutes.last().map_or(0, |m| after(m) + 1);
+
+    let change = |from: usize, wet: bool| minutes[from..].iter().position(|m| is_wet(m) == wet);
+
+    if is_wet(first) {
+        let Some(stop) = change(0, false) else {
+            return format!("Rain for at least the next {} min", span);
+        };
+        match change(stop, true) {
+            Some(again) => format!(
+                "Rain stopping in {} min, starting again in {} min",
+                after(&minutes[stop]),
+                after(&minutes[stop + again])
+            ),
+            None => format!("Rain stopping in {} min", after(&minutes[stop])),
+        }
+    } else {
+        let Some(start) = change(0, true) else {
+            return format!("No rain within the next {} min", span);
+        };
+        match change(start, false) {
+            Some(stop) => format!(
+                "Rain starting in {} min, stopping in {} min",
+                after(&minutes[start]),
+                after(&minutes[start + stop])
+            ),
+            None => format!(
+                "Rain starting in {} min and lasting for at least {} min",
+                after(&minutes[start]),
+                span - after(&minutes[start])
+            ),
+        }
+    }
+}
+
+fn is_wet(minute: &MinutePrecip) -> bool {
+    minute.precip >= WET_INTENSITY
+}
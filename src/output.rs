@@ -0,0 +1,209 @@
+use clap::ValueEnum;
+use serde_derive::Serialize;
+
+use crate::{
+    providers::chain::ChainForecast,
+    weather::{
+        cardinal_dir_from, Coordinates, CurrentWeather, HourWeather, TimeFormat, UnitType, Weather,
+    },
+    Result,
+};
+
+/// Version of the JSON document produced by `--output json`.
+/// Bump it whenever a field is renamed or removed.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Hours of the forecast listed in the waybar tooltip.
+pub const TOOLTIP_HOURS: usize = 6;
+
+#[derive(ValueEnum, Clone, Debug, Default)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+
+    /// Versioned JSON document
+    Json,
+
+    /// JSON line with text, tooltip and class for a waybar custom module
+    Waybar,
+
+    /// Full text, short text and color lines for i3blocks
+    I3blocks,
+
+    /// Single line with the current weather, e.g. for polybar
+    Line,
+}
+
+impl OutputFormat {
+    /// Whether the output shows the current weather in a desktop status bar.
+    pub fn is_status_bar(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Waybar | OutputFormat::I3blocks | OutputFormat::Line
+        )
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ForecastReport<'a> {
+    pub schema_version: u32,
+    pub provider: String,
+    pub place: Option<&'a str>,
+    pub coordinates: &'a Coordinates,
+    pub unit: &'a UnitType,
+    pub fetched_at: String,
+    pub from_cache: bool,
+    pub skipped: Vec<SkippedProvider<'a>>,
+    pub weather: &'a Weather,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SkippedProvider<'a> {
+    pub provider: &'a str,
+    pub reason: String,
+}
+
+impl<'a> ForecastReport<'a> {
+    pub fn new(
+        place: Option<&'a str>,
+        coordinates: &'a Coordinates,
+        unit: &'a UnitType,
+        result: &'a ChainForecast,
+    ) -> Self {
+        let skipped = result
+            .skipped
+            .iter()
+            .map(|s| SkippedProvider {
+                provider: &s.provider,
+                reason: s.error.to_string().trim_end().to_string(),
+            })
+            .collect();
+
+        ForecastReport {
+            schema_version: SCHEMA_VERSION,
+            provider: result.provider.to_owned(),
+            place,
+            coordinates,
+            unit,
+            fetched_at: result.forecast.fetched_at.to_rfc3339(),
+            from_cache: result.forecast.from_cache,
+            skipped,
+            weather: &result.forecast.weather,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Kind of weather a status bar can style, derived from the condition text.
+pub fn condition_class(condition: &str) -> &'static str {
+    let condition = condition.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| condition.contains(w));
+
+    if has(&["thunder", "storm"]) {
+        "storm"
+    } else if has(&["snow", "sleet", "blizzard", "ice"]) {
+        "snow"
+    } else if has(&["rain", "drizzle", "shower"]) {
+        "rain"
+    } else if has(&["fog", "mist", "haze"]) {
+        "fog"
+    } else if has(&["clear", "sunny"]) {
+        "clear"
+    } else if has(&["cloud", "overcast"]) {
+        "cloudy"
+    } else {
+        "unknown"
+    }
+}
+
+/// One-line summary of the current weather, e.g. "8°C light rain".
+pub fn summary(current: &CurrentWeather) -> String {
+    let (temp_unit, _, _, _) = current.unit.symbols();
+    format!("{:.0}{} {}", current.temp, temp_unit, current.condition)
+}
+
+/// Lines read by i3blocks: the full text, the short text and the color.
+pub fn i3blocks(current: &CurrentWeather) -> String {
+    let (temp_unit, _, _, _) = current.unit.symbols();
+    let color = match condition_class(&current.condition) {
+        "storm" => "#d75f5f",
+        "snow" => "#ffffff",
+        "rain" => "#5fafff",
+        "fog" => "#a8a8a8",
+        "clear" => "#ffd75f",
+        _ => "#d0d0d0",
+    };
+
+    format!(
+        "{}\n{:.0}{}\n{}\n",
+        summary(current),
+        current.temp,
+        temp_unit,
+        color
+    )
+}
+
+/// Status of a waybar custom module with `"return-type": "json"`.
+#[derive(Serialize, Debug)]
+pub struct WaybarStatus {
+    pub text: String,
+    pub alt: &'static str,
+    pub tooltip: String,
+    pub class: &'static str,
+}
+
+impl WaybarStatus {
+    /// Summary of the current weather with the next hours in the tooltip.
+    pub fn new(
+        provider: &str,
+        place: Option<&str>,
+        current: &CurrentWeather,
+        hours: &[HourWeather],
+        time_format: &TimeFormat,
+    ) -> Self {
+        let (temp_unit, speed_unit, _, _) = current.unit.symbols();
+        let class = condition_class(&current.condition);
+
+        let mut tooltip = match place {
+            Some(place) => format!("{}, {}\n", place, provider),
+            None => format!("{}\n", provider),
+        };
+        tooltip.push_str(&format!(
+            "Feels like {:.0}{}, humidity {:.0}%, wind {:.1} {} {}",
+            current.feels_like,
+            temp_unit,
+            current.humidity,
+            current.wind_speed,
+            speed_unit,
+            cardinal_dir_from(current.wind_deg)
+        ));
+        if !hours.is_empty() {
+            tooltip.push('\n');
+        }
+        for h in hours.iter().take(TOOLTIP_HOURS) {
+            tooltip.push_str(&format!(
+                "\n{}  {:.0}{}  {}",
+                time_format.time(&h.time),
+                h.temp,
+                temp_unit,
+                h.condition
+            ));
+        }
+
+        WaybarStatus {
+            text: summary(current),
+            alt: class,
+            tooltip,
+            class,
+        }
+    }
+
+    /// Single line, waybar reads a status per line.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
@@ -1,18 +1,265 @@
 use crate::{
-    weather::{self, Coordinates, ForecastTime, UnitType},
+    errors::AppError,
+    secret::ApiKey,
+    weather::{self, Coordinates, ForecastTime, HistoryRange, UnitType},
     Result,
 };
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use url::Url;
 
+pub mod chain;
+pub mod open_meteo;
 pub mod open_weather;
+pub mod replay;
 pub mod weather_api;
 
+/// Weather service client translating its responses into the common model.
 #[async_trait]
-pub trait Provider {
+pub trait Provider: Send + Sync {
+    /// What the provider is able to deliver, e.g. how far ahead and which fields.
+    fn capabilities(&self) -> &'static weather::Capabilities;
+
+    /// Shortest interval between refreshes that stays within the free plan's request limits.
+    fn min_interval(&self) -> Duration;
+
+    /// Fetches the forecast, the period is expected to be within the
+    /// [`Horizon`](weather::Horizon) of [`Provider::capabilities`].
     async fn get_forecast(
         &self,
         coord: Coordinates,
         time: ForecastTime,
         unit: UnitType,
     ) -> Result<weather::Weather>;
+
+    /// Looks up the past weather, a single date by the hour and a longer range by the day,
+    /// [`AppError::Unsupported`] if the provider keeps no history.
+    async fn get_history(
+        &self,
+        coord: Coordinates,
+        range: HistoryRange,
+        unit: UnitType,
+    ) -> Result<weather::Weather>;
+
+    /// Lists the active severe weather alerts, [`AppError::Unsupported`] if the provider has none.
+    async fn get_alerts(&self, coord: Coordinates) -> Result<Vec<weather::WeatherAlert>>;
+
+    /// Fetches the air quality now or for the next hours, [`AppError::Unsupported`]
+    /// if the provider has none or can't forecast it for the period.
+    async fn get_air_quality(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+    ) -> Result<weather::AirForecast>;
+
+    /// Forecasts the precipitation minute by minute for the next hour,
+    /// [`AppError::Unsupported`] if the provider has no nowcast.
+    async fn get_nowcast(&self, coord: Coordinates) -> Result<Vec<weather::MinutePrecip>>;
+
+    /// Looks up places matching the name, e.g. "Lviv" or "Lviv, UA".
+    async fn geocode(&self, query: &str) -> Result<Vec<weather::GeoLocation>>;
+
+    /// Finds the locality the coordinates belong to.
+    async fn reverse_geocode(&self, coord: Coordinates) -> Result<Option<weather::GeoLocation>>;
+}
+
+/// Providers the tool can query, by their ID, with their names and capabilities.
+pub const AVAILABLE: [(&str, &str, &weather::Capabilities); 3] = [
+    (
+        open_weather::ID,
+        "Open Weather",
+        &open_weather::CAPABILITIES,
+    ),
+    (weather_api::ID, "Weather API", &weather_api::CAPABILITIES),
+    (open_meteo::ID, "Open Meteo", &open_meteo::CAPABILITIES),
+];
+
+/// Rejects forecasts further than the provider can deliver before making a request.
+pub fn check_horizon(provider: &dyn Provider, time: &ForecastTime) -> Result<()> {
+    let max = provider.capabilities().horizon;
+
+    match *time {
+        ForecastTime::Hours(n) if n > max.hours => Err(AppError::HorizonExceeded(format!(
+            "{} hours were requested, but at most {} hours are available",
+            n, max.hours
+        ))),
+        ForecastTime::Days(n) if n > max.days => Err(AppError::HorizonExceeded(format!(
+            "{} days were requested, but at most {} days are available",
+            n, max.days
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Rejects a reply with fewer hours or days than requested, e.g. because the plan
+/// of the API key forecasts less far ahead than the provider can.
+pub(crate) fn check_length<T>(entries: Vec<T>, requested: usize, what: &str) -> Result<Vec<T>> {
+    if entries.len() < requested {
+        return Err(AppError::HorizonExceeded(format!(
+            "{} {} were requested, but the provider returned {}",
+            requested,
+            what,
+            entries.len()
+        )));
+    }
+
+    Ok(entries)
+}
+
+/// Rejects requests for data the provider doesn't offer before making a request,
+/// e.g. `require(capabilities.alerts, "weather alerts are")`.
+pub fn require(offered: bool, what: &str) -> Result<()> {
+    if offered {
+        Ok(())
+    } else {
+        Err(AppError::Unsupported(format!("{} not offered", what)))
+    }
+}
+
+/// Creates the provider client for the configured provider, saving the replies
+/// to `record` if it is given.
+pub fn from_config(prv: &weather::Provider, record: Option<&Path>) -> Result<Box<dyn Provider>> {
+    let fetcher = |id| -> Result<Fetcher> {
+        let fetcher = Fetcher::new(id)?;
+        Ok(match record {
+            Some(dir) => fetcher.recording(dir),
+            None => fetcher,
+        })
+    };
+
+    Ok(match prv {
+        weather::Provider::OpenWeather(creds) => Box::new(
+            open_weather::OpenWeather::new(ApiKey::lazy(creds.clone()))?
+                .with_fetcher(fetcher(open_weather::ID)?),
+        ),
+        weather::Provider::WeatherApi(creds) => Box::new(
+            weather_api::WeatherApi::new(ApiKey::lazy(creds.clone()))?
+                .with_fetcher(fetcher(weather_api::ID)?),
+        ),
+        weather::Provider::OpenMeteo => {
+            Box::new(open_meteo::OpenMeteo::new()?.with_fetcher(fetcher(open_meteo::ID)?))
+        }
+        weather::Provider::Replay(source) => replay::from_dir(&source.dir)?,
+    })
+}
+
+/// Sends the provider requests, optionally recording the replies, or serves
+/// recorded replies instead of making requests.
+#[derive(Clone, Debug)]
+pub struct Fetcher {
+    provider: &'static str,
+    mode: FetchMode,
+}
+
+#[derive(Clone, Debug)]
+enum FetchMode {
+    Live {
+        client: Client,
+        record: Option<PathBuf>,
+    },
+    Replay(PathBuf),
+}
+
+impl Fetcher {
+    pub fn new(provider: &'static str) -> Result<Self> {
+        Ok(Fetcher {
+            provider,
+            mode: FetchMode::Live {
+                client: Client::builder().build()?,
+                record: None,
+            },
+        })
+    }
+
+    /// Saves every reply to the directory, see [`replay::Recording`].
+    pub fn recording(self, dir: &Path) -> Self {
+        let mode = match self.mode {
+            FetchMode::Live { client, .. } => FetchMode::Live {
+                client,
+                record: Some(dir.to_path_buf()),
+            },
+            mode => mode,
+        };
+
+        Fetcher { mode, ..self }
+    }
+
+    /// Serves the replies recorded in the directory instead of making requests.
+    pub fn replaying(provider: &'static str, dir: &Path) -> Self {
+        Fetcher {
+            provider,
+            mode: FetchMode::Replay(dir.to_path_buf()),
+        }
+    }
+
+    /// Fetches and decodes the reply, provider error replies are turned into
+    /// specific errors by `api_error` from the status and the body.
+    pub(crate) async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        query: &[(&str, String)],
+        api_error: impl Fn(StatusCode, &str) -> AppError,
+    ) -> Result<T> {
+        let (status, body) = match &self.mode {
+            FetchMode::Live { client, record } => {
+                let request = client.get(url).query(query).build()?;
+                let url = request.url().clone();
+
+                let response = client.execute(request).await?;
+                let status = response.status();
+                let body = response.text().await?;
+
+                if let Some(dir) = record {
+                    replay::Recording::new(self.provider, &url, status, &body).save(dir)?;
+                }
+
+                (status, body)
+            }
+            FetchMode::Replay(dir) => {
+                let mut url = url;
+                url.query_pairs_mut().extend_pairs(query);
+                let recording = replay::Recording::find(dir, self.provider, &url)?;
+                (recording.status()?, recording.body)
+            }
+        };
+
+        if !status.is_success() {
+            return Err(api_error(status, &body));
+        }
+
+        serde_json::from_str(&body).map_err(|e| AppError::ProviderSchemaChanged(e.to_string()))
+    }
+}
+
+/// Error for a reply whose body doesn't tell more than the status.
+pub(crate) fn status_error(status: StatusCode, message: String) -> AppError {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => AppError::InvalidKey(message),
+        StatusCode::TOO_MANY_REQUESTS => AppError::QuotaExceeded(message),
+        StatusCode::NOT_FOUND => AppError::LocationNotFound(message),
+        _ => AppError::ProviderResponse(status.as_u16(), message),
+    }
+}
+
+pub(crate) fn utc_offset(seconds: i32) -> Result<FixedOffset> {
+    FixedOffset::east_opt(seconds).ok_or(AppError::TimeParse(format!(
+        "Failed to parse provided UTC offset: {} seconds.",
+        seconds
+    )))
+}
+
+/// Unix timestamp as the local time of the forecast location.
+pub(crate) fn datetime_from(dt: i64, offset: &FixedOffset) -> Result<DateTime<FixedOffset>> {
+    Ok(DateTime::from_timestamp(dt, 0)
+        .ok_or(AppError::TimeParse(format!(
+            "Failed to parse provided timestamp: {} to DateTime.",
+            dt
+        )))?
+        .with_timezone(offset))
 }
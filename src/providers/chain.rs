@@ -0,0 +1,240 @@
+use std::{collections::HashSet, path::Path, time::Duration};
+
+use chrono::Utc;
+use futures::future::{join_all, BoxFuture, FutureExt};
+
+use crate::{
+    cache::{CachedForecast, CachedProvider},
+    config::WeatherConfig,
+    errors::AppError,
+    weather::{self, Coordinates, ForecastTime, GeoLocation, HistoryRange, UnitType},
+    Result,
+};
+
+use super::{from_config, Provider};
+
+/// Provider that was tried and failed before another one answered.
+#[derive(Debug)]
+pub struct Skipped {
+    pub provider: String,
+    pub error: AppError,
+}
+
+#[derive(Debug)]
+pub struct ChainForecast {
+    pub provider: String,
+    pub forecast: CachedForecast,
+    pub skipped: Vec<Skipped>,
+}
+
+#[derive(Debug)]
+pub struct ChainAlerts {
+    pub provider: String,
+    pub alerts: Vec<weather::WeatherAlert>,
+    pub skipped: Vec<Skipped>,
+}
+
+#[derive(Debug)]
+pub struct ChainAir {
+    pub provider: String,
+    pub air: weather::AirForecast,
+    pub skipped: Vec<Skipped>,
+}
+
+#[derive(Debug)]
+pub struct ChainNowcast {
+    pub provider: String,
+    pub minutes: Vec<weather::MinutePrecip>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Configured providers tried one after another until one of them answers.
+pub struct ProviderChain {
+    providers: Vec<(String, CachedProvider)>,
+}
+
+impl ProviderChain {
+    /// Builds the configured providers, saving their replies to `record` if it is given.
+    pub fn new(config: &WeatherConfig, offline: bool, record: Option<&Path>) -> Result<Self> {
+        if config.providers.is_empty() {
+            return Err(AppError::EmptyProvider);
+        }
+
+        let providers = config
+            .providers
+            .iter()
+            .map(|prv| {
+                let name = prv.to_string();
+                let provider = CachedProvider::new(
+                    from_config(prv, record)?,
+                    &prv.cache_id(),
+                    config.cache_ttl(),
+                    offline,
+                )?;
+                // Recording and replaying are about the replies, cached ones would hide them.
+                let provider = match (prv, record) {
+                    (weather::Provider::Replay(_), _) | (_, Some(_)) => provider.passthrough(),
+                    _ => provider,
+                };
+
+                Ok((name, provider))
+            })
+            .collect::<Result<Vec<(String, CachedProvider)>>>()?;
+
+        Ok(ProviderChain { providers })
+    }
+
+    /// Shortest refresh interval that none of the providers' request limits is exceeded by.
+    pub fn min_interval(&self) -> Duration {
+        self.providers
+            .iter()
+            .map(|(_, p)| p.min_interval())
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub async fn get_forecast(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+        unit: UnitType,
+    ) -> Result<ChainForecast> {
+        let (provider, forecast, skipped) = self
+            .try_in_order(|p| {
+                p.get_cached_forecast(coord.clone(), time.clone(), unit.clone())
+                    .boxed()
+            })
+            .await?;
+
+        Ok(ChainForecast {
+            provider,
+            forecast,
+            skipped,
+        })
+    }
+
+    /// Queries every configured provider at the same time.
+    pub async fn get_all(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+        unit: UnitType,
+    ) -> Vec<(String, Result<CachedForecast>)> {
+        let requests = self.providers.iter().map(|(name, provider)| {
+            let (coord, time, unit) = (coord.clone(), time.clone(), unit.clone());
+            async move {
+                let forecast = provider.get_cached_forecast(coord, time, unit).await;
+                (name.to_owned(), forecast)
+            }
+        });
+
+        join_all(requests).await
+    }
+
+    /// Looks up the past weather, which isn't cached.
+    pub async fn get_history(
+        &self,
+        coord: Coordinates,
+        range: HistoryRange,
+        unit: UnitType,
+    ) -> Result<ChainForecast> {
+        let (provider, weather, skipped) = self
+            .try_in_order(|p| p.get_history(coord.clone(), range.clone(), unit.clone()))
+            .await?;
+
+        Ok(ChainForecast {
+            provider,
+            forecast: CachedForecast {
+                weather,
+                fetched_at: Utc::now(),
+                from_cache: false,
+            },
+            skipped,
+        })
+    }
+
+    pub async fn get_alerts(&self, coord: Coordinates) -> Result<ChainAlerts> {
+        let (provider, alerts, skipped) =
+            self.try_in_order(|p| p.get_alerts(coord.clone())).await?;
+
+        Ok(ChainAlerts {
+            provider,
+            alerts,
+            skipped,
+        })
+    }
+
+    pub async fn get_air_quality(
+        &self,
+        coord: Coordinates,
+        time: ForecastTime,
+    ) -> Result<ChainAir> {
+        let (provider, air, skipped) = self
+            .try_in_order(|p| p.get_air_quality(coord.clone(), time.clone()))
+            .await?;
+
+        Ok(ChainAir {
+            provider,
+            air,
+            skipped,
+        })
+    }
+
+    pub async fn get_nowcast(&self, coord: Coordinates) -> Result<ChainNowcast> {
+        let (provider, minutes, skipped) =
+            self.try_in_order(|p| p.get_nowcast(coord.clone())).await?;
+
+        Ok(ChainNowcast {
+            provider,
+            minutes,
+            skipped,
+        })
+    }
+
+    /// Resolves the name to a single place, reporting the candidates when it is ambiguous.
+    pub async fn locate(&self, query: &str) -> Result<GeoLocation> {
+        let (_, mut found, _) = self.try_in_order(|p| p.geocode(query)).await?;
+
+        let mut seen = HashSet::new();
+        found.retain(|l| seen.insert((l.name.clone(), l.region.clone(), l.country.clone())));
+
+        match found.len() {
+            0 => Err(AppError::LocationNotFound(format!(
+                "no place matches \"{}\"",
+                query
+            ))),
+            1 => Ok(found.remove(0)),
+            _ => Err(AppError::AmbiguousLocation(query.to_string(), found)),
+        }
+    }
+
+    /// Finds the locality the coordinates belong to.
+    pub async fn locality(&self, coord: Coordinates) -> Result<Option<GeoLocation>> {
+        let (_, found, _) = self
+            .try_in_order(|p| p.reverse_geocode(coord.clone()))
+            .await?;
+
+        Ok(found)
+    }
+
+    /// Sends the request to the providers one by one until one of them answers.
+    async fn try_in_order<'a, T>(
+        &'a self,
+        request: impl Fn(&'a CachedProvider) -> BoxFuture<'a, Result<T>>,
+    ) -> Result<(String, T, Vec<Skipped>)> {
+        let mut skipped = Vec::new();
+
+        for (name, provider) in &self.providers {
+            match request(provider).await {
+                Ok(value) => return Ok((name.to_owned(), value, skipped)),
+                Err(error) if error.is_provider_failure() => skipped.push(Skipped {
+                    provider: name.to_owned(),
+                    error,
+                }),
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(AppError::AllProvidersFailed(skipped))
+    }
+}
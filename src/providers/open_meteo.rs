@@ -0,0 +1,460 @@
+use crate::{errors::AppError, weather, Result};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_derive::Deserialize;
+use url::Url;
+
+use super::{check_length, datetime_from, status_error, utc_offset, Fetcher, Provider};
+
+/// Name of the provider in recordings.
+pub const ID: &str = "open-meteo";
+
+const MAX_DAYS: usize = 16;
+
+pub const CAPABILITIES: weather::Capabilities = weather::Capabilities {
+    horizon: weather::Horizon {
+        // The first day is partly over, so the last one has to cover the remaining hours.
+        hours: (MAX_DAYS - 1) * 24,
+        days: MAX_DAYS,
+    },
+    current_fields: &["sunrise", "sunset", "precip"],
+    hourly_fields: &["precip"],
+    daily_fields: &[
+        "avg_temp", "pressure", "clouds", "precip", "sunrise", "sunset",
+    ],
+    alerts: false,
+    air_quality: None,
+    nowcast: false,
+    hourly_history: false,
+    daily_history: false,
+    // Conditions are named after the WMO weather codes.
+    languages: &["en"],
+    units: &[weather::UnitType::Metric, weather::UnitType::Imperial],
+};
+
+/// Variables requested for both the current conditions and the hourly forecast.
+const HOURLY_FIELDS: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,\
+    pressure_msl,cloud_cover,visibility,wind_speed_10m,wind_direction_10m,uv_index,\
+    precipitation,weather_code";
+
+const DAILY_FIELDS: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+    temperature_2m_mean,relative_humidity_2m_mean,pressure_msl_mean,cloud_cover_mean,\
+    wind_speed_10m_max,uv_index_max,precipitation_sum,sunrise,sunset";
+
+#[derive(Deserialize, Debug)]
+struct CurrentWeather {
+    time: i64,
+    temperature_2m: f32,
+    apparent_temperature: f32,
+    relative_humidity_2m: f32,
+    pressure_msl: f32,
+    cloud_cover: f32,
+    visibility: f32,
+    wind_speed_10m: f32,
+    wind_direction_10m: f32,
+    uv_index: Option<f32>,
+    precipitation: f32,
+    weather_code: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct HourlyForecast {
+    time: Vec<i64>,
+    temperature_2m: Vec<f32>,
+    apparent_temperature: Vec<f32>,
+    relative_humidity_2m: Vec<f32>,
+    pressure_msl: Vec<f32>,
+    cloud_cover: Vec<f32>,
+    visibility: Vec<Option<f32>>,
+    wind_speed_10m: Vec<f32>,
+    wind_direction_10m: Vec<f32>,
+    uv_index: Vec<Option<f32>>,
+    precipitation: Vec<f32>,
+    weather_code: Vec<u8>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DailyForecast {
+    time: Vec<i64>,
+    weather_code: Vec<u8>,
+    temperature_2m_max: Vec<f32>,
+    temperature_2m_min: Vec<f32>,
+    temperature_2m_mean: Vec<Option<f32>>,
+    relative_humidity_2m_mean: Vec<f32>,
+    pressure_msl_mean: Vec<Option<f32>>,
+    cloud_cover_mean: Vec<Option<f32>>,
+    wind_speed_10m_max: Vec<f32>,
+    uv_index_max: Vec<Option<f32>>,
+    precipitation_sum: Vec<Option<f32>>,
+    sunrise: Vec<i64>,
+    sunset: Vec<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeocodingData {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeocodingResult {
+    name: String,
+    latitude: f32,
+    longitude: f32,
+    country_code: Option<String>,
+    country: Option<String>,
+    admin1: Option<String>,
+}
+
+impl GeocodingResult {
+    fn matches_country(&self, country: &str) -> bool {
+        [&self.country_code, &self.country]
+            .into_iter()
+            .flatten()
+            .any(|c| c.eq_ignore_ascii_case(country))
+    }
+}
+
+impl From<GeocodingResult> for weather::GeoLocation {
+    fn from(value: GeocodingResult) -> Self {
+        weather::GeoLocation {
+            name: value.name,
+            region: value.admin1,
+            country: value.country_code.or(value.country).unwrap_or_default(),
+            coordinates: weather::Coordinates {
+                lat: value.latitude,
+                lon: value.longitude,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    reason: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct WeatherData {
+    utc_offset_seconds: i32,
+    current: CurrentWeather,
+    hourly: HourlyForecast,
+    daily: DailyForecast,
+}
+
+impl WeatherData {
+    pub fn parse_to_current(self, unit: weather::UnitType) -> Result<weather::CurrentWeather> {
+        let offset = utc_offset(self.utc_offset_seconds)?;
+        let (sunrise, sunset) = match (self.daily.sunrise.first(), self.daily.sunset.first()) {
+            (Some(&sunrise), Some(&sunset)) => (
+                Some(datetime_from(sunrise, &offset)?),
+                Some(datetime_from(sunset, &offset)?),
+            ),
+            _ => (None, None),
+        };
+
+        Ok(weather::CurrentWeather {
+            temp: self.current.temperature_2m,
+            feels_like: self.current.apparent_temperature,
+            visibility: visibility_from_meters(self.current.visibility, &unit),
+            clouds: self.current.cloud_cover,
+            humidity: self.current.relative_humidity_2m,
+            pressure: self.current.pressure_msl,
+            wind_speed: self.current.wind_speed_10m,
+            wind_deg: self.current.wind_direction_10m,
+            uvi: self.current.uv_index.unwrap_or_default(),
+            sunrise,
+            sunset,
+            condition: condition_from_code(self.current.weather_code).to_string(),
+            precip: Some(self.current.precipitation),
+            unit,
+        })
+    }
+
+    pub fn parse_to_today(
+        self,
+        n_hours: usize,
+        unit: weather::UnitType,
+    ) -> Result<Vec<weather::HourWeather>> {
+        let offset = utc_offset(self.utc_offset_seconds)?;
+        let current_utc = self.current.time;
+        let h = self.hourly;
+
+        (0..h.time.len())
+            // Get current hour and the following ones
+            .filter(|&i| h.time[i] - current_utc > -3600)
+            .take(n_hours)
+            .map(|i| {
+                Ok(weather::HourWeather {
+                    time: datetime_from(h.time[i], &offset)?,
+                    temp: h.temperature_2m[i],
+                    feels_like: h.apparent_temperature[i],
+                    visibility: visibility_from_meters(h.visibility[i].unwrap_or_default(), &unit),
+                    clouds: h.cloud_cover[i],
+                    humidity: h.relative_humidity_2m[i],
+                    pressure: h.pressure_msl[i],
+                    wind_speed: h.wind_speed_10m[i],
+                    wind_deg: h.wind_direction_10m[i],
+                    uvi: h.uv_index[i].unwrap_or_default(),
+                    condition: condition_from_code(h.weather_code[i]).to_string(),
+                    precip: Some(h.precipitation[i]),
+                    unit: unit.clone(),
+                })
+            })
+            .collect::<Result<Vec<weather::HourWeather>>>()
+            .and_then(|hours| check_length(hours, n_hours, "hours"))
+    }
+
+    pub fn parse_to_days(
+        self,
+        n_days: usize,
+        unit: weather::UnitType,
+    ) -> Result<Vec<weather::DailyWeather>> {
+        let offset = utc_offset(self.utc_offset_seconds)?;
+        let d = self.daily;
+
+        (0..d.time.len())
+            .take(n_days)
+            .map(|i| {
+                Ok(weather::DailyWeather {
+                    date: datetime_from(d.time[i], &offset)?.date_naive(),
+                    min_temp: d.temperature_2m_min[i],
+                    max_temp: d.temperature_2m_max[i],
+                    avg_temp: d.temperature_2m_mean[i],
+                    visibility: None,
+                    humidity: d.relative_humidity_2m_mean[i],
+                    pressure: d.pressure_msl_mean[i],
+                    wind_speed: d.wind_speed_10m_max[i],
+                    uvi: d.uv_index_max[i].unwrap_or_default(),
+                    clouds: d.cloud_cover_mean[i],
+                    condition: condition_from_code(d.weather_code[i]).to_string(),
+                    precip: d.precipitation_sum[i],
+                    sunrise: Some(datetime_from(d.sunrise[i], &offset)?),
+                    sunset: Some(datetime_from(d.sunset[i], &offset)?),
+                    moonrise: None,
+                    moonset: None,
+                    moon_phase: None,
+                    unit: unit.clone(),
+                })
+            })
+            .collect::<Result<Vec<weather::DailyWeather>>>()
+            .and_then(|days| check_length(days, n_days, "days"))
+    }
+}
+
+pub struct OpenMeteo {
+    fetcher: Fetcher,
+    base_url: Url,
+    geocoding_url: Url,
+}
+
+impl OpenMeteo {
+    pub fn new() -> Result<Self> {
+        Self::with_base_urls(
+            Url::parse("https://api.open-meteo.com")?,
+            Url::parse("https://geocoding-api.open-meteo.com")?,
+        )
+    }
+
+    /// Sends the requests through the fetcher, e.g. to record the replies.
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        OpenMeteo { fetcher, ..self }
+    }
+
+    /// Sends the requests to other hosts, e.g. a local stand-in in tests.
+    pub fn with_base_urls(base_url: Url, geocoding_url: Url) -> Result<Self> {
+        Ok(OpenMeteo {
+            fetcher: Fetcher::new(ID)?,
+            base_url,
+            geocoding_url,
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for OpenMeteo {
+    fn capabilities(&self) -> &'static weather::Capabilities {
+        &CAPABILITIES
+    }
+
+    fn min_interval(&self) -> Duration {
+        // The free API allows 10,000 calls a day.
+        Duration::from_secs(10)
+    }
+
+    async fn get_forecast(
+        &self,
+        coord: weather::Coordinates,
+        time: weather::ForecastTime,
+        unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/v1/forecast");
+
+        let (temperature_unit, wind_speed_unit, precipitation_unit) = match unit {
+            weather::UnitType::Metric => ("celsius", "ms", "mm"),
+            weather::UnitType::Imperial => ("fahrenheit", "mph", "inch"),
+        };
+
+        let query = [
+            ("latitude", coord.lat.to_string()),
+            ("longitude", coord.lon.to_string()),
+            ("current", HOURLY_FIELDS.to_string()),
+            ("hourly", HOURLY_FIELDS.to_string()),
+            ("daily", DAILY_FIELDS.to_string()),
+            ("forecast_days", parse_forecast_time(&time).to_string()),
+            ("temperature_unit", temperature_unit.to_string()),
+            ("wind_speed_unit", wind_speed_unit.to_string()),
+            ("precipitation_unit", precipitation_unit.to_string()),
+            ("timeformat", "unixtime".to_string()),
+            ("timezone", "auto".to_string()),
+        ];
+
+        let weather_data = self
+            .fetcher
+            .get_json::<WeatherData>(url, &query, api_error)
+            .await?;
+
+        Ok(match time {
+            weather::ForecastTime::Now => {
+                weather::Weather::Current(weather_data.parse_to_current(unit)?)
+            }
+            weather::ForecastTime::Hours(n) => {
+                weather::Weather::Today(weather_data.parse_to_today(n, unit)?)
+            }
+            weather::ForecastTime::Days(n) => {
+                weather::Weather::Daily(weather_data.parse_to_days(n, unit)?)
+            }
+        })
+    }
+
+    async fn get_history(
+        &self,
+        _coord: weather::Coordinates,
+        _range: weather::HistoryRange,
+        _unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        Err(AppError::Unsupported(
+            "Open Meteo keeps past weather in a separate archive, which isn't supported"
+                .to_string(),
+        ))
+    }
+
+    async fn get_alerts(&self, _coord: weather::Coordinates) -> Result<Vec<weather::WeatherAlert>> {
+        Err(AppError::Unsupported(
+            "Open Meteo does not provide weather alerts".to_string(),
+        ))
+    }
+
+    async fn get_air_quality(
+        &self,
+        _coord: weather::Coordinates,
+        _time: weather::ForecastTime,
+    ) -> Result<weather::AirForecast> {
+        Err(AppError::Unsupported(
+            "Open Meteo does not provide air quality".to_string(),
+        ))
+    }
+
+    async fn get_nowcast(
+        &self,
+        _coord: weather::Coordinates,
+    ) -> Result<Vec<weather::MinutePrecip>> {
+        Err(AppError::Unsupported(
+            "Open Meteo has no minute by minute forecast".to_string(),
+        ))
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<weather::GeoLocation>> {
+        let mut url = self.geocoding_url.to_owned();
+        url.set_path("/v1/search");
+
+        // The geocoding API searches by name only, so the country is matched here.
+        let mut parts = query.split(',').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let country = parts.next_back();
+
+        let query = [("name", name.to_string()), ("count", "10".to_string())];
+
+        let data = self
+            .fetcher
+            .get_json::<GeocodingData>(url, &query, api_error)
+            .await?;
+
+        Ok(data
+            .results
+            .into_iter()
+            .filter(|r| country.is_none_or(|c| r.matches_country(c)))
+            .map(weather::GeoLocation::from)
+            .collect())
+    }
+
+    async fn reverse_geocode(
+        &self,
+        _coord: weather::Coordinates,
+    ) -> Result<Option<weather::GeoLocation>> {
+        Err(AppError::Unsupported(
+            "Open Meteo does not provide reverse geocoding".to_string(),
+        ))
+    }
+}
+
+fn parse_forecast_time(time: &weather::ForecastTime) -> usize {
+    match time {
+        weather::ForecastTime::Now => 1,
+        weather::ForecastTime::Hours(n) => (n.div_ceil(24) + 1).min(MAX_DAYS),
+        weather::ForecastTime::Days(n) => *n,
+    }
+}
+
+/// Open-Meteo always reports visibility in meters.
+fn visibility_from_meters(meters: f32, unit: &weather::UnitType) -> f32 {
+    match unit {
+        weather::UnitType::Metric => meters / 1000.0,
+        weather::UnitType::Imperial => meters / 1609.344,
+    }
+}
+
+/// Describes the WMO weather interpretation code used by Open-Meteo.
+fn condition_from_code(code: u8) -> &'static str {
+    match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 => "Fog",
+        48 => "Depositing rime fog",
+        51 => "Light drizzle",
+        53 => "Moderate drizzle",
+        55 => "Dense drizzle",
+        56 => "Light freezing drizzle",
+        57 => "Dense freezing drizzle",
+        61 => "Slight rain",
+        63 => "Moderate rain",
+        65 => "Heavy rain",
+        66 => "Light freezing rain",
+        67 => "Heavy freezing rain",
+        71 => "Slight snow fall",
+        73 => "Moderate snow fall",
+        75 => "Heavy snow fall",
+        77 => "Snow grains",
+        80 => "Slight rain showers",
+        81 => "Moderate rain showers",
+        82 => "Violent rain showers",
+        85 => "Slight snow showers",
+        86 => "Heavy snow showers",
+        95 => "Thunderstorm",
+        96 => "Thunderstorm with slight hail",
+        99 => "Thunderstorm with heavy hail",
+        _ => "No data",
+    }
+}
+
+fn api_error(status: StatusCode, body: &str) -> AppError {
+    let message =
+        serde_json::from_str::<ErrorResponse>(body).map_or(status.to_string(), |e| e.reason);
+
+    status_error(status, message)
+}
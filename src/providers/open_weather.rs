@@ -1,13 +1,46 @@
-use crate::{errors::AppError, weather, Result};
+use crate::{errors::AppError, secret::ApiKey, weather, Result};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use reqwest::{self, Client};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+use futures::future::join_all;
+use reqwest::StatusCode;
 use serde_derive::Deserialize;
 use url::Url;
 
-use chrono::{self, DateTime, FixedOffset};
+use super::{check_length, datetime_from, status_error, utc_offset, Fetcher, Provider};
 
-use super::Provider;
+/// Name of the provider in recordings.
+pub const ID: &str = "open-weather";
+
+pub const CAPABILITIES: weather::Capabilities = weather::Capabilities {
+    // One Call returns 48 hourly and 8 daily forecasts.
+    horizon: weather::Horizon { hours: 48, days: 8 },
+    current_fields: &["sunrise", "sunset", "precip"],
+    hourly_fields: &["precip"],
+    daily_fields: &[
+        "pressure",
+        "clouds",
+        "precip",
+        "sunrise",
+        "sunset",
+        "moonrise",
+        "moonset",
+        "moon_phase",
+    ],
+    alerts: true,
+    air_quality: Some(weather::AqiScale::OpenWeather),
+    // Covered regions only, the reply tells whether the location is one of them.
+    nowcast: true,
+    // Timemachine answers with a single hour.
+    hourly_history: true,
+    daily_history: false,
+    languages: &["en"],
+    units: &[weather::UnitType::Metric, weather::UnitType::Imperial],
+};
+
+/// Timemachine requests in flight at once while fetching a past day.
+const HISTORY_BATCH: usize = 4;
 
 #[derive(Deserialize, Debug)]
 struct CurrentWeather {
@@ -53,6 +86,33 @@ struct HourlyForecast {
     weather: Vec<WeatherCondition>,
 }
 
+impl HourlyForecast {
+    fn parse(self, offset: &FixedOffset, unit: weather::UnitType) -> Result<weather::HourWeather> {
+        let precip = self.rain.map(|r| r.mm_h);
+        let condition = self
+            .weather
+            .into_iter()
+            .next()
+            .map_or("No data".to_string(), |w| w.description);
+
+        Ok(weather::HourWeather {
+            time: datetime_from(self.dt, offset)?,
+            temp: self.temp,
+            feels_like: self.feels_like,
+            visibility: self.visibility / 1000.0,
+            clouds: self.clouds,
+            humidity: self.humidity,
+            pressure: self.pressure,
+            wind_speed: self.wind_speed,
+            wind_deg: self.wind_deg,
+            uvi: self.uvi,
+            condition,
+            precip,
+            unit,
+        })
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct DailyForecast {
     dt: i64,
@@ -77,17 +137,132 @@ struct DailyTemperature {
     max: f32,
 }
 
+#[derive(Deserialize, Debug)]
+struct Alert {
+    sender_name: String,
+    event: String,
+    start: i64,
+    end: i64,
+    description: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeoPlace {
+    name: String,
+    lat: f32,
+    lon: f32,
+    country: String,
+    state: Option<String>,
+}
+
+impl From<GeoPlace> for weather::GeoLocation {
+    fn from(value: GeoPlace) -> Self {
+        weather::GeoLocation {
+            name: value.name,
+            region: value.state,
+            country: value.country,
+            coordinates: weather::Coordinates {
+                lat: value.lat,
+                lon: value.lon,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AirPollution {
+    list: Vec<AirSample>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AirSample {
+    dt: i64,
+    main: AirIndex,
+    components: AirComponents,
+}
+
+#[derive(Deserialize, Debug)]
+struct AirIndex {
+    aqi: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct AirComponents {
+    co: f32,
+    no2: f32,
+    o3: f32,
+    so2: f32,
+    pm2_5: f32,
+    pm10: f32,
+}
+
+impl AirSample {
+    /// The air pollution API doesn't report the time zone, so times are in UTC.
+    fn parse(self) -> Result<weather::AirQuality> {
+        Ok(weather::AirQuality {
+            time: datetime_from(self.dt, &utc_offset(0)?)?,
+            aqi: self.main.aqi,
+            scale: weather::AqiScale::OpenWeather,
+            co: self.components.co,
+            no2: self.components.no2,
+            o3: self.components.o3,
+            so2: self.components.so2,
+            pm2_5: self.components.pm2_5,
+            pm10: self.components.pm10,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct MinuteForecast {
+    dt: i64,
+    precipitation: f32,
+}
+
+/// One Call reply with everything but the minutely forecast excluded.
+#[derive(Deserialize, Debug)]
+struct Nowcast {
+    timezone_offset: i32,
+    /// Missing for locations the nowcast doesn't cover
+    #[serde(default)]
+    minutely: Vec<MinuteForecast>,
+}
+
+impl Nowcast {
+    fn parse_to_minutes(self) -> Result<Vec<weather::MinutePrecip>> {
+        let offset = utc_offset(self.timezone_offset)?;
+
+        self.minutely
+            .into_iter()
+            .map(|m| {
+                Ok(weather::MinutePrecip {
+                    time: datetime_from(m.dt, &offset)?,
+                    precip: m.precipitation,
+                })
+            })
+            .collect::<Result<Vec<weather::MinutePrecip>>>()
+    }
+}
+
+/// Body of the error replies, `cod` repeats the HTTP status.
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    message: String,
+}
+
 #[derive(Deserialize, Debug)]
 struct WeatherData {
-    timezone_offset: u32,
+    timezone_offset: i32,
     current: CurrentWeather,
     hourly: Vec<HourlyForecast>,
     daily: Vec<DailyForecast>,
+    #[serde(default)]
+    alerts: Vec<Alert>,
 }
 
 impl WeatherData {
     pub fn parse_to_current(self, unit: weather::UnitType) -> Result<weather::CurrentWeather> {
-        let offset = FixedOffset::east_opt(self.timezone_offset as i32);
+        let offset = utc_offset(self.timezone_offset)?;
 
         let precip = self.current.rain.map(|r| r.mm_h);
         let condition = self
@@ -107,53 +282,27 @@ impl WeatherData {
             wind_speed: self.current.wind_speed,
             wind_deg: self.current.wind_deg,
             uvi: self.current.uvi,
-            sunrise: Some(datetime_to_str(
-                self.current.sunrise,
-                offset.as_ref(),
-                "%H:%M",
-            )?),
-            sunset: Some(datetime_to_str(
-                self.current.sunset,
-                offset.as_ref(),
-                "%H:%M",
-            )?),
+            sunrise: event_time(self.current.sunrise, &offset)?,
+            sunset: event_time(self.current.sunset, &offset)?,
             condition,
             precip,
             unit,
         })
     }
 
-    pub fn parse_to_today(self, unit: weather::UnitType) -> Result<Vec<weather::HourWeather>> {
-        let offset = FixedOffset::east_opt(self.timezone_offset as i32);
+    pub fn parse_to_today(
+        self,
+        n_hours: usize,
+        unit: weather::UnitType,
+    ) -> Result<Vec<weather::HourWeather>> {
+        let offset = utc_offset(self.timezone_offset)?;
 
         self.hourly
             .into_iter()
-            .take(24)
-            .map(|h| {
-                let precip = h.rain.map(|r| r.mm_h);
-                let condition = h
-                    .weather
-                    .into_iter()
-                    .next()
-                    .map_or("No data".to_string(), |w| w.description);
-
-                Ok(weather::HourWeather {
-                    time: datetime_to_str(Some(h.dt), offset.as_ref(), "%Y-%m-%d %H:%M")?,
-                    temp: h.temp,
-                    feels_like: h.feels_like,
-                    visibility: h.visibility / 1000.0,
-                    clouds: h.clouds,
-                    humidity: h.humidity,
-                    pressure: h.pressure,
-                    wind_speed: h.wind_speed,
-                    wind_deg: h.wind_deg,
-                    uvi: h.uvi,
-                    condition,
-                    precip,
-                    unit: unit.clone(),
-                })
-            })
+            .take(n_hours)
+            .map(|h| h.parse(&offset, unit.clone()))
             .collect::<Result<Vec<weather::HourWeather>>>()
+            .and_then(|hours| check_length(hours, n_hours, "hours"))
     }
 
     pub fn parse_to_days(
@@ -161,7 +310,7 @@ impl WeatherData {
         n_days: usize,
         unit: weather::UnitType,
     ) -> Result<Vec<weather::DailyWeather>> {
-        let offset = FixedOffset::east_opt(self.timezone_offset as i32);
+        let offset = utc_offset(self.timezone_offset)?;
 
         self.daily
             .into_iter()
@@ -174,7 +323,7 @@ impl WeatherData {
                     .map_or("No data".to_string(), |w| w.description);
 
                 Ok(weather::DailyWeather {
-                    date: datetime_to_str(Some(d.dt), offset.as_ref(), "%Y-%m-%d")?,
+                    date: datetime_from(d.dt, &offset)?.date_naive(),
                     min_temp: d.temp.min,
                     max_temp: d.temp.max,
                     avg_temp: None,
@@ -186,96 +335,373 @@ impl WeatherData {
                     condition,
                     precip: d.rain,
                     clouds: Some(d.clouds),
-                    sunrise: Some(datetime_to_str(d.sunrise, offset.as_ref(), "%H:%M")?),
-                    sunset: Some(datetime_to_str(d.sunset, offset.as_ref(), "%H:%M")?),
-                    moonrise: Some(datetime_to_str(d.moonrise, offset.as_ref(), "%H:%M")?),
-                    moonset: Some(datetime_to_str(d.moonset, offset.as_ref(), "%H:%M")?),
+                    sunrise: event_time(d.sunrise, &offset)?,
+                    sunset: event_time(d.sunset, &offset)?,
+                    moonrise: event_time(d.moonrise, &offset)?,
+                    moonset: event_time(d.moonset, &offset)?,
                     moon_phase: Some(format!("{:.2}", d.moon_phase)),
                     unit: unit.clone(),
                 })
             })
             .collect::<Result<Vec<weather::DailyWeather>>>()
+            .and_then(|days| check_length(days, n_days, "days"))
+    }
+
+    pub fn parse_to_alerts(self) -> Result<Vec<weather::WeatherAlert>> {
+        let offset = utc_offset(self.timezone_offset)?;
+
+        self.alerts
+            .into_iter()
+            .map(|a| {
+                Ok(weather::WeatherAlert {
+                    sender: Some(a.sender_name),
+                    event: a.event,
+                    severity: None,
+                    start: Some(datetime_from(a.start, &offset)?),
+                    end: Some(datetime_from(a.end, &offset)?),
+                    description: a.description,
+                })
+            })
+            .collect::<Result<Vec<weather::WeatherAlert>>>()
+    }
+}
+
+/// Reply of the timemachine endpoint, which holds the single hour that was asked for.
+#[derive(Deserialize, Debug)]
+struct TimeMachine {
+    timezone_offset: i32,
+    data: Vec<HourlyForecast>,
+}
+
+impl TimeMachine {
+    fn parse_to_hours(self, unit: &weather::UnitType) -> Result<Vec<weather::HourWeather>> {
+        let offset = utc_offset(self.timezone_offset)?;
+
+        self.data
+            .into_iter()
+            .map(|h| h.parse(&offset, unit.clone()))
+            .collect::<Result<Vec<weather::HourWeather>>>()
     }
 }
 
 pub struct OpenWeather {
-    client: Client,
+    fetcher: Fetcher,
     base_url: Url,
-    app_id: String,
+    app_id: ApiKey,
 }
 
 impl OpenWeather {
-    pub fn new(app_id: String) -> Result<Self> {
-        let base_url = Url::parse("https://api.openweathermap.org")?;
-        let client = reqwest::Client::builder().build()?;
+    pub fn new(app_id: impl Into<ApiKey>) -> Result<Self> {
+        Self::with_base_url(app_id, Url::parse("https://api.openweathermap.org")?)
+    }
 
+    /// Sends the requests through the fetcher, e.g. to record the replies.
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        OpenWeather { fetcher, ..self }
+    }
+
+    /// Sends the requests to another host, e.g. a local stand-in in tests.
+    pub fn with_base_url(app_id: impl Into<ApiKey>, base_url: Url) -> Result<Self> {
         Ok(OpenWeather {
-            client,
+            fetcher: Fetcher::new(ID)?,
             base_url,
-            app_id,
+            app_id: app_id.into(),
         })
     }
-}
 
-#[async_trait]
-impl Provider for OpenWeather {
-    async fn get_forecast(
+    async fn fetch_weather_data(
         &self,
         coord: weather::Coordinates,
-        time: weather::ForecastTime,
-        unit: weather::UnitType,
-    ) -> Result<weather::Weather> {
+        unit: &weather::UnitType,
+    ) -> Result<WeatherData> {
         let mut url = self.base_url.to_owned();
         url.set_path("/data/3.0/onecall");
 
         let query = [
             ("lat", coord.lat.to_string()),
             ("lon", coord.lon.to_string()),
-            ("appid", self.app_id.to_owned()),
+            ("appid", self.app_id.get()?),
             ("exclude", "minutely".to_string()),
             ("units", unit.to_string().to_lowercase()),
         ];
 
         let weather_data = self
-            .client
-            .get(url)
-            .query(&query)
-            .send()
-            .await?
-            .json::<WeatherData>()
+            .fetcher
+            .get_json::<WeatherData>(url, &query, api_error)
             .await?;
 
+        Ok(weather_data)
+    }
+
+    async fn fetch_time_machine(
+        &self,
+        coord: weather::Coordinates,
+        dt: i64,
+        unit: &weather::UnitType,
+    ) -> Result<TimeMachine> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/data/3.0/onecall/timemachine");
+
+        let query = [
+            ("lat", coord.lat.to_string()),
+            ("lon", coord.lon.to_string()),
+            ("dt", dt.to_string()),
+            ("appid", self.app_id.get()?),
+            ("units", unit.to_string().to_lowercase()),
+        ];
+
+        self.fetcher
+            .get_json::<TimeMachine>(url, &query, api_error)
+            .await
+    }
+
+    /// Timemachine answers with a single hour, so the day takes a request per hour.
+    async fn fetch_history_day(
+        &self,
+        coord: weather::Coordinates,
+        date: NaiveDate,
+        unit: &weather::UnitType,
+    ) -> Result<Vec<weather::HourWeather>> {
+        // The first reply tells the UTC offset, which the location's midnight depends on.
+        let noon = date.and_time(NaiveTime::MIN).and_utc().timestamp() + 12 * 3600;
+        let first = self.fetch_time_machine(coord.clone(), noon, unit).await?;
+        let midnight = noon - 12 * 3600 - first.timezone_offset as i64;
+
+        let times: Vec<i64> = (0..24)
+            .map(|h| midnight + h * 3600)
+            .filter(|&dt| dt != noon)
+            .collect();
+
+        let offset = utc_offset(first.timezone_offset)?;
+        let mut hours = first.parse_to_hours(unit)?;
+        let mut missing = Vec::new();
+        for batch in times.chunks(HISTORY_BATCH) {
+            let requests = batch
+                .iter()
+                .map(|&dt| self.fetch_time_machine(coord.clone(), dt, unit));
+            for (&dt, reply) in batch.iter().zip(join_all(requests).await) {
+                match reply.and_then(|r| r.parse_to_hours(unit)) {
+                    Ok(reply) => hours.extend(reply),
+                    // A dropped connection or a server hiccup only loses the hour,
+                    // a rejected key or an exhausted quota would lose every one.
+                    Err(e) if is_transient(&e) => missing.push((datetime_from(dt, &offset)?, e)),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        if let Some((_, error)) = missing.first() {
+            return Err(AppError::IncompleteHistory(format!(
+                "no reply for {} on {}, {}",
+                missing
+                    .iter()
+                    .map(|(time, _)| time.format("%H:%M").to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                date,
+                error.to_string().trim_end()
+            )));
+        }
+        hours.retain(|h| h.time.date_naive() == date);
+        hours.sort_by_key(|h| h.time);
+        hours.dedup_by_key(|h| h.time);
+
+        Ok(hours)
+    }
+
+    async fn fetch_air_quality(
+        &self,
+        coord: weather::Coordinates,
+        path: &str,
+    ) -> Result<Vec<weather::AirQuality>> {
+        let mut url = self.base_url.to_owned();
+        url.set_path(path);
+
+        let query = [
+            ("lat", coord.lat.to_string()),
+            ("lon", coord.lon.to_string()),
+            ("appid", self.app_id.get()?),
+        ];
+
+        self.fetcher
+            .get_json::<AirPollution>(url, &query, api_error)
+            .await?
+            .list
+            .into_iter()
+            .map(AirSample::parse)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Provider for OpenWeather {
+    fn capabilities(&self) -> &'static weather::Capabilities {
+        &CAPABILITIES
+    }
+
+    fn min_interval(&self) -> Duration {
+        // The free One Call plan allows 1,000 calls a day.
+        Duration::from_secs(90)
+    }
+
+    async fn get_forecast(
+        &self,
+        coord: weather::Coordinates,
+        time: weather::ForecastTime,
+        unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        let weather_data = self.fetch_weather_data(coord, &unit).await?;
+
         Ok(match time {
             weather::ForecastTime::Now => {
                 weather::Weather::Current(weather_data.parse_to_current(unit)?)
             }
-            weather::ForecastTime::Hours24 => {
-                weather::Weather::Today(weather_data.parse_to_today(unit)?)
-            }
-            weather::ForecastTime::Days3 => {
-                weather::Weather::Daily(weather_data.parse_to_days(3, unit)?)
+            weather::ForecastTime::Hours(n) => {
+                weather::Weather::Today(weather_data.parse_to_today(n, unit)?)
             }
-            weather::ForecastTime::Days5 => {
-                weather::Weather::Daily(weather_data.parse_to_days(5, unit)?)
+            weather::ForecastTime::Days(n) => {
+                weather::Weather::Daily(weather_data.parse_to_days(n, unit)?)
             }
         })
     }
+
+    async fn get_history(
+        &self,
+        coord: weather::Coordinates,
+        range: weather::HistoryRange,
+        unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        if !range.is_single_day() {
+            return Err(AppError::Unsupported(
+                "Open Weather looks up the past by the hour, pick a single date".to_string(),
+            ));
+        }
+
+        Ok(weather::Weather::Today(
+            self.fetch_history_day(coord, range.from, &unit).await?,
+        ))
+    }
+
+    async fn get_alerts(&self, coord: weather::Coordinates) -> Result<Vec<weather::WeatherAlert>> {
+        self.fetch_weather_data(coord, &weather::UnitType::Metric)
+            .await?
+            .parse_to_alerts()
+    }
+
+    async fn get_air_quality(
+        &self,
+        coord: weather::Coordinates,
+        time: weather::ForecastTime,
+    ) -> Result<weather::AirForecast> {
+        match time {
+            weather::ForecastTime::Now => self
+                .fetch_air_quality(coord, "/data/2.5/air_pollution")
+                .await?
+                .into_iter()
+                .next()
+                .map(weather::AirForecast::Current)
+                .ok_or(AppError::ProviderSchemaChanged(
+                    "the air quality reply holds no data".to_string(),
+                )),
+            weather::ForecastTime::Hours(n) => {
+                let hours = self
+                    .fetch_air_quality(coord, "/data/2.5/air_pollution/forecast")
+                    .await?;
+                Ok(weather::AirForecast::Hourly(
+                    hours.into_iter().take(n).collect(),
+                ))
+            }
+            weather::ForecastTime::Days(_) => Err(AppError::Unsupported(
+                "Open Weather forecasts air quality by the hour only".to_string(),
+            )),
+        }
+    }
+
+    async fn get_nowcast(&self, coord: weather::Coordinates) -> Result<Vec<weather::MinutePrecip>> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/data/3.0/onecall");
+
+        let query = [
+            ("lat", coord.lat.to_string()),
+            ("lon", coord.lon.to_string()),
+            ("appid", self.app_id.get()?),
+            ("exclude", "current,hourly,daily,alerts".to_string()),
+        ];
+
+        let minutes = self
+            .fetcher
+            .get_json::<Nowcast>(url, &query, api_error)
+            .await?
+            .parse_to_minutes()?;
+
+        if minutes.is_empty() {
+            return Err(AppError::Unsupported(
+                "Open Weather has no minute by minute forecast for the location".to_string(),
+            ));
+        }
+        Ok(minutes)
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<weather::GeoLocation>> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/geo/1.0/direct");
+
+        let query = [
+            ("q", query.to_string()),
+            ("limit", "5".to_string()),
+            ("appid", self.app_id.get()?),
+        ];
+
+        let places = self
+            .fetcher
+            .get_json::<Vec<GeoPlace>>(url, &query, api_error)
+            .await?;
+
+        Ok(places.into_iter().map(weather::GeoLocation::from).collect())
+    }
+
+    async fn reverse_geocode(
+        &self,
+        coord: weather::Coordinates,
+    ) -> Result<Option<weather::GeoLocation>> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/geo/1.0/reverse");
+
+        let query = [
+            ("lat", coord.lat.to_string()),
+            ("lon", coord.lon.to_string()),
+            ("limit", "1".to_string()),
+            ("appid", self.app_id.get()?),
+        ];
+
+        let places = self
+            .fetcher
+            .get_json::<Vec<GeoPlace>>(url, &query, api_error)
+            .await?;
+
+        Ok(places.into_iter().next().map(weather::GeoLocation::from))
+    }
 }
 
-fn datetime_to_str(dt: Option<i64>, offset: Option<&FixedOffset>, fmt: &str) -> Result<String> {
-    let datetime = dt.ok_or(AppError::TimeParse(
-        "Failed to get UTC timestamp".to_string(),
-    ))?;
-    let offset = offset.ok_or(AppError::TimeParse(
-        "Failed to parse provided UTC offset".to_string(),
-    ))?;
-
-    Ok(DateTime::from_timestamp(datetime, 0)
-        .ok_or(AppError::TimeParse(format!(
-            "Failed to parse provided timestamp: {} to DateTime.",
-            datetime
-        )))?
-        .with_timezone(offset)
-        .format(fmt)
-        .to_string())
+/// Sun and moon events are missing, or reported as 0, on days they don't happen,
+/// e.g. during the polar day and night.
+fn event_time(dt: Option<i64>, offset: &FixedOffset) -> Result<Option<DateTime<FixedOffset>>> {
+    dt.filter(|&dt| dt != 0)
+        .map(|dt| datetime_from(dt, offset))
+        .transpose()
+}
+
+/// Failures that may not repeat on the next request, unlike a rejected key.
+fn is_transient(error: &AppError) -> bool {
+    match error {
+        AppError::ApiRequest(_) => true,
+        AppError::ProviderResponse(status, _) => *status >= 500,
+        _ => false,
+    }
+}
+
+fn api_error(status: StatusCode, body: &str) -> AppError {
+    let message =
+        serde_json::from_str::<ErrorResponse>(body).map_or(status.to_string(), |e| e.message);
+
+    status_error(status, message)
 }
@@ -0,0 +1,157 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde_derive::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{errors::AppError, Result};
+
+use super::{open_meteo, open_weather, weather_api, Fetcher, Provider};
+
+/// Query parameters carrying API keys, never written to recordings.
+const SECRET_PARAMS: [&str; 2] = ["appid", "key"];
+
+/// Number of the next recording of the run, keeps file names apart.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Raw provider reply saved by `--record`, one file per request.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Recording {
+    pub provider: String,
+    /// Request URL with the API key redacted
+    pub url: String,
+    pub status: u16,
+    pub body: String,
+}
+
+impl Recording {
+    pub fn new(provider: &str, url: &Url, status: StatusCode, body: &str) -> Self {
+        Recording {
+            provider: provider.to_string(),
+            url: redacted(url).to_string(),
+            status: status.as_u16(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Writes the recording to a new file, names sort in the order of the requests.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = Url::parse(&self.url)?
+            .path()
+            .trim_matches('/')
+            .replace('/', "-");
+        // Concurrent requests to the same path may finish within the same millisecond.
+        let name = format!(
+            "{}_{:04}_{}_{}.json",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            self.provider,
+            path
+        );
+
+        fs::create_dir_all(dir)?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(name))?
+            .write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// The latest reply of the provider to the request, matched on the path and
+    /// the query with the API key redacted.
+    pub fn find(dir: &Path, provider: &str, url: &Url) -> Result<Self> {
+        let request = redacted(url);
+        read_all(dir)?
+            .into_iter()
+            .rev()
+            .find(|r| {
+                r.provider == provider
+                    && Url::parse(&r.url).is_ok_and(|url| {
+                        url.path() == request.path() && url.query_pairs().eq(request.query_pairs())
+                    })
+            })
+            .ok_or(AppError::NoRecording(format!(
+                "no recording for this request: {} {}?{} in {}",
+                provider,
+                request.path(),
+                request.query().unwrap_or_default(),
+                dir.display()
+            )))
+    }
+
+    pub fn status(&self) -> Result<StatusCode> {
+        StatusCode::from_u16(self.status)
+            .map_err(|e| AppError::NoRecording(format!("invalid status {}: {}", self.status, e)))
+    }
+}
+
+/// Provider that parses the replies recorded in the directory, it is the provider
+/// of the latest recording.
+pub fn from_dir(dir: &Path) -> Result<Box<dyn Provider>> {
+    let provider = read_all(dir)?
+        .pop()
+        .map(|r| r.provider)
+        .ok_or(AppError::NoRecording(format!(
+            "no recordings in {}",
+            dir.display()
+        )))?;
+
+    Ok(match provider.as_str() {
+        open_weather::ID => Box::new(
+            open_weather::OpenWeather::new(String::new())?
+                .with_fetcher(Fetcher::replaying(open_weather::ID, dir)),
+        ),
+        weather_api::ID => Box::new(
+            weather_api::WeatherApi::new(String::new())?
+                .with_fetcher(Fetcher::replaying(weather_api::ID, dir)),
+        ),
+        open_meteo::ID => Box::new(
+            open_meteo::OpenMeteo::new()?.with_fetcher(Fetcher::replaying(open_meteo::ID, dir)),
+        ),
+        other => {
+            return Err(AppError::NoRecording(format!(
+                "unknown provider {} in {}",
+                other,
+                dir.display()
+            )))
+        }
+    })
+}
+
+/// The URL with the values of the API key parameters replaced.
+fn redacted(url: &Url) -> Url {
+    let mut url = url.clone();
+    let query = url
+        .query_pairs()
+        .map(
+            |(name, value)| match SECRET_PARAMS.contains(&name.as_ref()) {
+                true => (name.into_owned(), "REDACTED".to_string()),
+                false => (name.into_owned(), value.into_owned()),
+            },
+        )
+        .collect::<Vec<(String, String)>>();
+    url.query_pairs_mut().clear().extend_pairs(query);
+    url
+}
+
+/// Recordings in the directory, oldest first.
+fn read_all(dir: &Path) -> Result<Vec<Recording>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| Ok(serde_json::from_str(&fs::read_to_string(path)?)?))
+        .collect()
+}
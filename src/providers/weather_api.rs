@@ -1,32 +1,82 @@
-use crate::{weather, Result};
+use crate::{errors::AppError, secret::ApiKey, weather, Result};
 use serde_derive::Deserialize;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 
 use async_trait::async_trait;
-use reqwest::{self, Client};
+use reqwest::StatusCode;
 use url::Url;
 
-use chrono;
+use super::{check_length, datetime_from, status_error, utc_offset, Fetcher, Provider};
+
+/// Name of the provider in recordings.
+pub const ID: &str = "weather-api";
 
-use super::Provider;
+/// Longest forecast offered by the paid plans, the free one stops at 3 days.
+const MAX_DAYS: usize = 14;
+
+pub const CAPABILITIES: weather::Capabilities = weather::Capabilities {
+    horizon: weather::Horizon {
+        // The first day is partly over, so the last one has to cover the remaining hours.
+        hours: (MAX_DAYS - 1) * 24,
+        days: MAX_DAYS,
+    },
+    current_fields: &["sunrise", "sunset", "precip"],
+    hourly_fields: &["precip"],
+    daily_fields: &[
+        "precip",
+        "sunrise",
+        "sunset",
+        "moonrise",
+        "moonset",
+        "moon_phase",
+    ],
+    alerts: true,
+    air_quality: Some(weather::AqiScale::UsEpa),
+    nowcast: false,
+    hourly_history: true,
+    // Date ranges need a paid plan.
+    daily_history: true,
+    languages: &["en"],
+    units: &[weather::UnitType::Metric, weather::UnitType::Imperial],
+};
+
+#[derive(Deserialize, Debug)]
+pub struct ErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ApiError {
+    code: u32,
+    message: String,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct WeatherData {
+    location: Location,
     current: Current,
     forecast: Forecast,
+    #[serde(default)]
+    alerts: Alerts,
 }
 
 impl WeatherData {
-    pub fn parse_to_current(self, unit: weather::UnitType) -> weather::CurrentWeather {
-        let (sunset, sunrise) = self
-            .forecast
-            .forecastday
-            .into_iter()
-            .next()
-            .map_or((None, None), |d| {
-                (Some(d.astro.sunrise), Some(d.astro.sunset))
-            });
+    pub fn parse_to_current(self, unit: weather::UnitType) -> Result<weather::CurrentWeather> {
+        let offset = self.location.utc_offset()?;
+        let (sunrise, sunset) = match self.forecast.forecastday.into_iter().next() {
+            Some(d) => {
+                let date = parse_date(&d.date)?;
+                (
+                    parse_astro_time(&d.astro.sunrise, date, &offset),
+                    parse_astro_time(&d.astro.sunset, date, &offset),
+                )
+            }
+            None => (None, None),
+        };
 
-        weather::CurrentWeather {
+        Ok(weather::CurrentWeather {
             temp: match unit {
                 weather::UnitType::Metric => self.current.temp_c,
                 weather::UnitType::Imperial => self.current.temp_f,
@@ -56,94 +106,139 @@ impl WeatherData {
                 weather::UnitType::Imperial => self.current.precip_in,
             }),
             unit,
-        }
+        })
     }
 
-    pub fn parse_to_today(self, unit: weather::UnitType) -> Vec<weather::HourWeather> {
-        let current_utc = chrono::Utc::now().timestamp();
+    pub fn parse_to_today(
+        self,
+        n_hours: usize,
+        unit: weather::UnitType,
+    ) -> Result<Vec<weather::HourWeather>> {
+        let offset = self.location.utc_offset()?;
+        let current_utc = self.location.localtime_epoch;
 
         self.forecast
             .forecastday
             .into_iter()
             .flat_map(|d| d.hour)
-            // Get current hour and next 23
+            // Get current hour and the following ones
             .filter(|h| h.time_epoch - current_utc > -3600)
-            .take(24)
-            .map(|h| weather::HourWeather {
-                time: h.time.clone(),
-                temp: match unit {
-                    weather::UnitType::Metric => h.temp_c,
-                    weather::UnitType::Imperial => h.temp_f,
-                },
-                feels_like: match unit {
-                    weather::UnitType::Metric => h.feelslike_c,
-                    weather::UnitType::Imperial => h.feelslike_f,
-                },
-                visibility: match unit {
-                    weather::UnitType::Metric => h.vis_km,
-                    weather::UnitType::Imperial => h.vis_miles,
-                },
-                clouds: h.cloud,
-                humidity: h.humidity,
-                pressure: h.pressure_mb,
-                wind_speed: match unit {
-                    weather::UnitType::Metric => h.wind_kph / 3.6,
-                    weather::UnitType::Imperial => h.wind_mph,
-                },
-                wind_deg: h.wind_degree as f32,
-                uvi: h.uv,
-                condition: h.condition.text,
-                precip: Some(match unit {
-                    weather::UnitType::Metric => h.precip_mm,
-                    weather::UnitType::Imperial => h.precip_in,
-                }),
-                unit: unit.to_owned(),
-            })
-            .collect::<Vec<weather::HourWeather>>()
+            .take(n_hours)
+            .map(|h| h.parse(&offset, unit.clone()))
+            .collect::<Result<Vec<weather::HourWeather>>>()
+            .and_then(|hours| check_length(hours, n_hours, "hours"))
     }
 
     pub fn parse_to_days(
         self,
         n_days: usize,
         unit: weather::UnitType,
-    ) -> Vec<weather::DailyWeather> {
+    ) -> Result<Vec<weather::DailyWeather>> {
+        let offset = self.location.utc_offset()?;
+
         self.forecast
             .forecastday
             .into_iter()
             .take(n_days)
-            .map(|d| weather::DailyWeather {
-                date: d.date,
-                min_temp: match unit {
-                    weather::UnitType::Metric => d.day.mintemp_c,
-                    weather::UnitType::Imperial => d.day.mintemp_f,
-                },
-                max_temp: match unit {
-                    weather::UnitType::Metric => d.day.maxtemp_c,
-                    weather::UnitType::Imperial => d.day.maxtemp_f,
-                },
-                avg_temp: None,
-                visibility: None,
-                humidity: d.day.avghumidity,
-                pressure: None,
-                wind_speed: match unit {
-                    weather::UnitType::Metric => d.day.maxwind_kph / 3.6,
-                    weather::UnitType::Imperial => d.day.maxwind_mph,
-                },
-                uvi: d.day.uv,
-                condition: d.day.condition.text,
-                precip: Some(match unit {
-                    weather::UnitType::Metric => d.day.totalprecip_mm,
-                    weather::UnitType::Imperial => d.day.totalprecip_in,
-                }),
-                clouds: None,
-                sunrise: Some(d.astro.sunrise),
-                sunset: Some(d.astro.sunset),
-                moonrise: Some(d.astro.moonrise),
-                moonset: Some(d.astro.moonset),
-                moon_phase: Some(d.astro.moon_phase),
-                unit: unit.clone(),
+            .map(|d| d.parse(&offset, unit.clone()))
+            .collect::<Result<Vec<weather::DailyWeather>>>()
+            .and_then(|days| check_length(days, n_days, "days"))
+    }
+
+    pub fn parse_to_current_air(self) -> Result<weather::AirQuality> {
+        let time = datetime_from(self.location.localtime_epoch, &self.location.utc_offset()?)?;
+
+        self.current
+            .air_quality
+            .map(|a| a.parse(time))
+            .ok_or_else(no_air_quality)
+    }
+
+    pub fn parse_to_hourly_air(self, n_hours: usize) -> Result<Vec<weather::AirQuality>> {
+        let offset = self.location.utc_offset()?;
+        let current_utc = self.location.localtime_epoch;
+
+        let hours = self
+            .forecast
+            .forecastday
+            .into_iter()
+            .flat_map(|d| d.hour)
+            .filter(|h| h.time_epoch - current_utc > -3600)
+            .take(n_hours)
+            .filter_map(|h| {
+                let time = datetime_from(h.time_epoch, &offset);
+                h.air_quality.map(|a| Ok(a.parse(time?)))
+            })
+            .collect::<Result<Vec<weather::AirQuality>>>()?;
+
+        if hours.is_empty() && n_hours > 0 {
+            return Err(no_air_quality());
+        }
+        Ok(hours)
+    }
+
+    pub fn parse_to_alerts(self) -> Vec<weather::WeatherAlert> {
+        self.alerts
+            .alert
+            .into_iter()
+            .map(|a| weather::WeatherAlert {
+                sender: None,
+                event: a.event,
+                severity: a.severity.filter(|s| !s.is_empty()),
+                start: DateTime::parse_from_rfc3339(&a.effective).ok(),
+                end: DateTime::parse_from_rfc3339(&a.expires).ok(),
+                description: a.desc,
             })
-            .collect::<Vec<weather::DailyWeather>>()
+            .collect::<Vec<weather::WeatherAlert>>()
+    }
+}
+
+/// Reply of `history.json`, laid out like a forecast without the current weather.
+#[derive(Deserialize, Debug)]
+pub struct HistoryData {
+    location: Location,
+    forecast: Forecast,
+}
+
+impl HistoryData {
+    pub fn parse_to_hours(self, unit: weather::UnitType) -> Result<Vec<weather::HourWeather>> {
+        let offset = self.location.utc_offset()?;
+
+        self.forecast
+            .forecastday
+            .into_iter()
+            .flat_map(|d| d.hour)
+            .map(|h| h.parse(&offset, unit.clone()))
+            .collect::<Result<Vec<weather::HourWeather>>>()
+    }
+
+    pub fn parse_to_days(self, unit: weather::UnitType) -> Result<Vec<weather::DailyWeather>> {
+        let offset = self.location.utc_offset()?;
+
+        self.forecast
+            .forecastday
+            .into_iter()
+            .map(|d| d.parse(&offset, unit.clone()))
+            .collect::<Result<Vec<weather::DailyWeather>>>()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Location {
+    localtime_epoch: i64,
+    localtime: String,
+}
+
+impl Location {
+    /// WeatherAPI reports only the local time, the offset is the difference to UTC.
+    fn utc_offset(&self) -> Result<FixedOffset> {
+        let local = NaiveDateTime::parse_from_str(&self.localtime, "%Y-%m-%d %H:%M")
+            .map_err(|e| AppError::TimeParse(format!("Failed to parse local time: {}", e)))?;
+        let utc = datetime_from(self.localtime_epoch, &utc_offset(0)?)?.naive_utc();
+
+        // Local time has no seconds, while all the offsets in use are multiples of 15 minutes.
+        let seconds = (local - utc).num_seconds() as f64;
+        utc_offset(((seconds / 900.0).round() * 900.0) as i32)
     }
 }
 
@@ -165,6 +260,36 @@ pub struct Current {
     vis_km: f32,
     vis_miles: f32,
     uv: f32,
+    air_quality: Option<AirData>,
+}
+
+/// Concentrations in μg/m³ reported with `aqi=yes`.
+#[derive(Deserialize, Debug)]
+pub struct AirData {
+    co: f32,
+    no2: f32,
+    o3: f32,
+    so2: f32,
+    pm2_5: f32,
+    pm10: f32,
+    #[serde(rename = "us-epa-index")]
+    us_epa_index: u8,
+}
+
+impl AirData {
+    fn parse(self, time: DateTime<FixedOffset>) -> weather::AirQuality {
+        weather::AirQuality {
+            time,
+            aqi: self.us_epa_index,
+            scale: weather::AqiScale::UsEpa,
+            co: self.co,
+            no2: self.no2,
+            o3: self.o3,
+            so2: self.so2,
+            pm2_5: self.pm2_5,
+            pm10: self.pm10,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -185,6 +310,45 @@ pub struct ForecastDay {
     hour: Vec<Hour>,
 }
 
+impl ForecastDay {
+    fn parse(self, offset: &FixedOffset, unit: weather::UnitType) -> Result<weather::DailyWeather> {
+        let date = parse_date(&self.date)?;
+
+        Ok(weather::DailyWeather {
+            date,
+            min_temp: match unit {
+                weather::UnitType::Metric => self.day.mintemp_c,
+                weather::UnitType::Imperial => self.day.mintemp_f,
+            },
+            max_temp: match unit {
+                weather::UnitType::Metric => self.day.maxtemp_c,
+                weather::UnitType::Imperial => self.day.maxtemp_f,
+            },
+            avg_temp: None,
+            visibility: None,
+            humidity: self.day.avghumidity,
+            pressure: None,
+            wind_speed: match unit {
+                weather::UnitType::Metric => self.day.maxwind_kph / 3.6,
+                weather::UnitType::Imperial => self.day.maxwind_mph,
+            },
+            uvi: self.day.uv,
+            condition: self.day.condition.text,
+            precip: Some(match unit {
+                weather::UnitType::Metric => self.day.totalprecip_mm,
+                weather::UnitType::Imperial => self.day.totalprecip_in,
+            }),
+            clouds: None,
+            sunrise: parse_astro_time(&self.astro.sunrise, date, offset),
+            sunset: parse_astro_time(&self.astro.sunset, date, offset),
+            moonrise: parse_astro_time(&self.astro.moonrise, date, offset),
+            moonset: parse_astro_time(&self.astro.moonset, date, offset),
+            moon_phase: Some(self.astro.moon_phase),
+            unit,
+        })
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Day {
     maxtemp_c: f32,
@@ -209,10 +373,46 @@ pub struct Astro {
     moon_phase: String,
 }
 
+#[derive(Deserialize, Default, Debug)]
+pub struct Alerts {
+    alert: Vec<Alert>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Alert {
+    event: String,
+    severity: Option<String>,
+    effective: String,
+    expires: String,
+    desc: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SearchResult {
+    name: String,
+    region: String,
+    country: String,
+    lat: f32,
+    lon: f32,
+}
+
+impl From<SearchResult> for weather::GeoLocation {
+    fn from(value: SearchResult) -> Self {
+        weather::GeoLocation {
+            name: value.name,
+            region: Some(value.region).filter(|r| !r.is_empty()),
+            country: value.country,
+            coordinates: weather::Coordinates {
+                lat: value.lat,
+                lon: value.lon,
+            },
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Hour {
     time_epoch: i64,
-    time: String,
     temp_c: f32,
     temp_f: f32,
     condition: Condition,
@@ -229,76 +429,272 @@ pub struct Hour {
     vis_km: f32,
     vis_miles: f32,
     uv: f32,
+    air_quality: Option<AirData>,
+}
+
+impl Hour {
+    fn parse(self, offset: &FixedOffset, unit: weather::UnitType) -> Result<weather::HourWeather> {
+        Ok(weather::HourWeather {
+            time: datetime_from(self.time_epoch, offset)?,
+            temp: match unit {
+                weather::UnitType::Metric => self.temp_c,
+                weather::UnitType::Imperial => self.temp_f,
+            },
+            feels_like: match unit {
+                weather::UnitType::Metric => self.feelslike_c,
+                weather::UnitType::Imperial => self.feelslike_f,
+            },
+            visibility: match unit {
+                weather::UnitType::Metric => self.vis_km,
+                weather::UnitType::Imperial => self.vis_miles,
+            },
+            clouds: self.cloud,
+            humidity: self.humidity,
+            pressure: self.pressure_mb,
+            wind_speed: match unit {
+                weather::UnitType::Metric => self.wind_kph / 3.6,
+                weather::UnitType::Imperial => self.wind_mph,
+            },
+            wind_deg: self.wind_degree as f32,
+            uvi: self.uv,
+            condition: self.condition.text,
+            precip: Some(match unit {
+                weather::UnitType::Metric => self.precip_mm,
+                weather::UnitType::Imperial => self.precip_in,
+            }),
+            unit,
+        })
+    }
 }
 
 pub struct WeatherApi {
-    client: Client,
+    fetcher: Fetcher,
     base_url: Url,
-    key: String,
+    key: ApiKey,
 }
 
 impl WeatherApi {
-    pub fn new(key: String) -> Result<Self> {
-        let base_url = Url::parse("https://api.weatherapi.com")?;
-        let client = reqwest::Client::builder().build()?;
+    pub fn new(key: impl Into<ApiKey>) -> Result<Self> {
+        Self::with_base_url(key, Url::parse("https://api.weatherapi.com")?)
+    }
+
+    /// Sends the requests through the fetcher, e.g. to record the replies.
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        WeatherApi { fetcher, ..self }
+    }
 
+    /// Sends the requests to another host, e.g. a local stand-in in tests.
+    pub fn with_base_url(key: impl Into<ApiKey>, base_url: Url) -> Result<Self> {
         Ok(WeatherApi {
-            client,
+            fetcher: Fetcher::new(ID)?,
             base_url,
-            key,
+            key: key.into(),
         })
     }
-}
 
-#[async_trait]
-impl Provider for WeatherApi {
-    async fn get_forecast(
+    async fn fetch_weather_data(
         &self,
         coord: weather::Coordinates,
-        time: weather::ForecastTime,
-        unit: weather::UnitType,
-    ) -> Result<weather::Weather> {
+        days: usize,
+        aqi: bool,
+    ) -> Result<WeatherData> {
         let mut url = self.base_url.to_owned();
         url.set_path("/v1/forecast.json");
 
         let query = [
             ("q", format!("{},{}", coord.lat, coord.lon)),
-            ("key", self.key.to_string()),
-            ("days", parse_forecast_time(&time).to_string()),
+            ("key", self.key.get()?),
+            ("days", days.to_string()),
             ("alerts", "yes".to_string()),
+            ("aqi", if aqi { "yes" } else { "no" }.to_string()),
         ];
 
         let weather_data = self
-            .client
-            .get(url)
-            .query(&query)
-            .send()
-            .await?
-            .json::<WeatherData>()
+            .fetcher
+            .get_json::<WeatherData>(url, &query, api_error)
+            .await?;
+
+        Ok(weather_data)
+    }
+
+    /// Ranges longer than a day are available on the paid plans only.
+    async fn fetch_history(
+        &self,
+        coord: weather::Coordinates,
+        range: &weather::HistoryRange,
+    ) -> Result<HistoryData> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/v1/history.json");
+
+        let mut query = vec![
+            ("q", format!("{},{}", coord.lat, coord.lon)),
+            ("key", self.key.get()?),
+            ("dt", range.from.to_string()),
+        ];
+        if !range.is_single_day() {
+            query.push(("end_dt", range.to.to_string()));
+        }
+
+        self.fetcher
+            .get_json::<HistoryData>(url, &query, api_error)
+            .await
+    }
+}
+
+#[async_trait]
+impl Provider for WeatherApi {
+    fn capabilities(&self) -> &'static weather::Capabilities {
+        &CAPABILITIES
+    }
+
+    fn min_interval(&self) -> Duration {
+        // The free plan allows 1,000,000 calls a month.
+        Duration::from_secs(3)
+    }
+
+    async fn get_forecast(
+        &self,
+        coord: weather::Coordinates,
+        time: weather::ForecastTime,
+        unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        let weather_data = self
+            .fetch_weather_data(coord, parse_forecast_time(&time), false)
             .await?;
 
         Ok(match time {
             weather::ForecastTime::Now => {
-                weather::Weather::Current(weather_data.parse_to_current(unit))
+                weather::Weather::Current(weather_data.parse_to_current(unit)?)
             }
-            weather::ForecastTime::Hours24 => {
-                weather::Weather::Today(weather_data.parse_to_today(unit))
+            weather::ForecastTime::Hours(n) => {
+                weather::Weather::Today(weather_data.parse_to_today(n, unit)?)
             }
-            weather::ForecastTime::Days3 => {
-                weather::Weather::Daily(weather_data.parse_to_days(3, unit))
+            weather::ForecastTime::Days(n) => {
+                weather::Weather::Daily(weather_data.parse_to_days(n, unit)?)
             }
-            weather::ForecastTime::Days5 => {
-                weather::Weather::Daily(weather_data.parse_to_days(5, unit))
+        })
+    }
+
+    async fn get_history(
+        &self,
+        coord: weather::Coordinates,
+        range: weather::HistoryRange,
+        unit: weather::UnitType,
+    ) -> Result<weather::Weather> {
+        let history = self.fetch_history(coord, &range).await?;
+
+        Ok(if range.is_single_day() {
+            weather::Weather::Today(history.parse_to_hours(unit)?)
+        } else {
+            weather::Weather::Daily(history.parse_to_days(unit)?)
+        })
+    }
+
+    async fn get_alerts(&self, coord: weather::Coordinates) -> Result<Vec<weather::WeatherAlert>> {
+        Ok(self
+            .fetch_weather_data(coord, 1, false)
+            .await?
+            .parse_to_alerts())
+    }
+
+    async fn get_air_quality(
+        &self,
+        coord: weather::Coordinates,
+        time: weather::ForecastTime,
+    ) -> Result<weather::AirForecast> {
+        if let weather::ForecastTime::Days(_) = time {
+            return Err(AppError::Unsupported(
+                "Weather API forecasts air quality by the hour only".to_string(),
+            ));
+        }
+
+        let weather_data = self
+            .fetch_weather_data(coord, parse_forecast_time(&time), true)
+            .await?;
+
+        Ok(match time {
+            weather::ForecastTime::Hours(n) => {
+                weather::AirForecast::Hourly(weather_data.parse_to_hourly_air(n)?)
             }
+            _ => weather::AirForecast::Current(weather_data.parse_to_current_air()?),
         })
     }
+
+    async fn get_nowcast(
+        &self,
+        _coord: weather::Coordinates,
+    ) -> Result<Vec<weather::MinutePrecip>> {
+        Err(AppError::Unsupported(
+            "Weather API has no minute by minute forecast".to_string(),
+        ))
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<weather::GeoLocation>> {
+        let mut url = self.base_url.to_owned();
+        url.set_path("/v1/search.json");
+
+        let query = [("q", query.to_string()), ("key", self.key.get()?)];
+
+        let results = self
+            .fetcher
+            .get_json::<Vec<SearchResult>>(url, &query, api_error)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(weather::GeoLocation::from)
+            .collect())
+    }
+
+    async fn reverse_geocode(
+        &self,
+        coord: weather::Coordinates,
+    ) -> Result<Option<weather::GeoLocation>> {
+        // Searching by coordinates returns the nearest localities first.
+        let query = format!("{},{}", coord.lat, coord.lon);
+        Ok(self.geocode(&query).await?.into_iter().next())
+    }
 }
 
 fn parse_forecast_time(time: &weather::ForecastTime) -> usize {
     match time {
         weather::ForecastTime::Now => 1,
-        weather::ForecastTime::Hours24 => 2,
-        weather::ForecastTime::Days3 => 3,
-        weather::ForecastTime::Days5 => 5,
+        weather::ForecastTime::Hours(n) => (n.div_ceil(24) + 1).min(MAX_DAYS),
+        weather::ForecastTime::Days(n) => *n,
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| AppError::TimeParse(format!("Failed to parse date {}: {}", date, e)))
+}
+
+/// Astronomical times come as e.g. "06:12 AM", or "No moonrise" when there is none that day.
+fn parse_astro_time(
+    time: &str,
+    date: NaiveDate,
+    offset: &FixedOffset,
+) -> Option<DateTime<FixedOffset>> {
+    NaiveTime::parse_from_str(time, "%I:%M %p")
+        .ok()
+        .and_then(|t| date.and_time(t).and_local_timezone(*offset).single())
+}
+
+fn no_air_quality() -> AppError {
+    AppError::Unsupported("the reply carries no air quality data for the plan".to_string())
+}
+
+/// Error codes are listed at https://www.weatherapi.com/docs/#intro-error-codes
+fn api_error(status: StatusCode, body: &str) -> AppError {
+    let Ok(ErrorResponse { error }) = serde_json::from_str::<ErrorResponse>(body) else {
+        return status_error(status, status.to_string());
+    };
+
+    match error.code {
+        1002 | 2006 | 2008 => AppError::InvalidKey(error.message),
+        2007 => AppError::QuotaExceeded(error.message),
+        1006 => AppError::LocationNotFound(error.message),
+        2009 => AppError::Unsupported(error.message),
+        _ => AppError::ProviderResponse(status.as_u16(), error.message),
     }
 }
@@ -0,0 +1,113 @@
+use std::{env, fs, path::Path, process::Command, sync::OnceLock};
+
+use crate::{errors::AppError, weather::ProviderCredentials, Result};
+
+/// API key read from its source right before the first request that needs it,
+/// so a broken source only fails the provider it belongs to.
+#[derive(Debug)]
+pub struct ApiKey {
+    source: Option<ProviderCredentials>,
+    key: OnceLock<String>,
+}
+
+impl ApiKey {
+    /// Key resolved from the credentials on first use and kept for later requests.
+    pub fn lazy(credentials: ProviderCredentials) -> Self {
+        ApiKey {
+            source: Some(credentials),
+            key: OnceLock::new(),
+        }
+    }
+
+    pub fn get(&self) -> Result<String> {
+        if let Some(key) = self.key.get() {
+            return Ok(key.to_owned());
+        }
+
+        let key = match &self.source {
+            Some(credentials) => credentials.resolve_key()?,
+            None => String::new(),
+        };
+        Ok(self.key.get_or_init(|| key).to_owned())
+    }
+}
+
+impl From<String> for ApiKey {
+    fn from(key: String) -> Self {
+        ApiKey {
+            source: None,
+            key: OnceLock::from(key),
+        }
+    }
+}
+
+pub fn from_env(var: &str) -> Result<String> {
+    env::var(var)
+        .map(|key| key.trim().to_string())
+        .map_err(|e| AppError::Secret(format!("environment variable {}: {}", var, e)))
+}
+
+/// Runs the command through the shell and takes its first line of output as the key.
+pub fn from_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }
+    .map_err(|e| AppError::Secret(format!("failed to run `{}`: {}", command, e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Secret(format!(
+            "`{}` failed with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next().map(str::trim) {
+        Some(key) if !key.is_empty() => Ok(key.to_string()),
+        _ => Err(AppError::Secret(format!("`{}` printed no key", command))),
+    }
+}
+
+pub fn from_file(path: &Path) -> Result<String> {
+    if is_shared(path)? {
+        return Err(AppError::Secret(format!(
+            "{} is readable by other users, restrict it with `chmod 600`",
+            path.display()
+        )));
+    }
+
+    let key = fs::read_to_string(path)
+        .map_err(|e| AppError::Secret(format!("{}: {}", path.display(), e)))?;
+
+    Ok(key.trim().to_string())
+}
+
+/// Whether users other than the owner have any access to the file.
+pub fn is_shared(path: &Path) -> Result<bool> {
+    Ok(mode(path)? & 0o077 != 0)
+}
+
+/// Whether everyone on the machine may read the file.
+pub fn is_world_readable(path: &Path) -> Result<bool> {
+    Ok(mode(path)? & 0o004 != 0)
+}
+
+#[cfg(unix)]
+fn mode(path: &Path) -> Result<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(fs::metadata(path)
+        .map_err(|e| AppError::Secret(format!("{}: {}", path.display(), e)))?
+        .permissions()
+        .mode())
+}
+
+/// Permissions can't be told from the mode elsewhere, so files are treated as private.
+#[cfg(not(unix))]
+fn mode(_path: &Path) -> Result<u32> {
+    Ok(0o600)
+}
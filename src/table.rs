@@ -0,0 +1,182 @@
+use clap::ValueEnum;
+
+use crate::weather::{cardinal_dir_from, DailyWeather, HourWeather, TimeFormat};
+
+/// Condition texts are cut to no less than this many characters before columns are dropped.
+const MIN_CONDITION_WIDTH: usize = 10;
+
+/// How hourly and daily forecasts are laid out as text.
+#[derive(ValueEnum, Clone, Copy, Default, Debug)]
+pub enum Layout {
+    /// One row per hour or day
+    #[default]
+    Table,
+    /// One block of lines per hour or day
+    Blocks,
+}
+
+/// Forecast field shown as a table column.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum Column {
+    Time,
+    Temp,
+    FeelsLike,
+    Wind,
+    Precip,
+    Condition,
+    Humidity,
+    Clouds,
+    Uvi,
+}
+
+/// Columns shown unless others are picked with `--columns`.
+pub const DEFAULT_COLUMNS: [Column; 6] = [
+    Column::Time,
+    Column::Temp,
+    Column::FeelsLike,
+    Column::Wind,
+    Column::Precip,
+    Column::Condition,
+];
+
+impl Column {
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Time => "Time",
+            Column::Temp => "Temp",
+            Column::FeelsLike => "Feels like",
+            Column::Wind => "Wind",
+            Column::Precip => "Precip",
+            Column::Condition => "Condition",
+            Column::Humidity => "Humidity",
+            Column::Clouds => "Clouds",
+            Column::Uvi => "UV",
+        }
+    }
+
+    fn hour_cell(&self, h: &HourWeather, time_format: &TimeFormat) -> String {
+        let (temp_unit, speed_unit, _, precip_unit) = h.unit.symbols();
+        match self {
+            Column::Time => time_format.datetime(&h.time),
+            Column::Temp => format!("{:.1} {}", h.temp, temp_unit),
+            Column::FeelsLike => format!("{:.1} {}", h.feels_like, temp_unit),
+            Column::Wind => format!(
+                "{:.1} {} {}",
+                h.wind_speed,
+                speed_unit,
+                cardinal_dir_from(h.wind_deg)
+            ),
+            Column::Precip => optional(h.precip, precip_unit),
+            Column::Condition => h.condition.clone(),
+            Column::Humidity => format!("{:.0}%", h.humidity),
+            Column::Clouds => format!("{:.0}%", h.clouds),
+            Column::Uvi => format!("{:.1}", h.uvi),
+        }
+    }
+
+    fn day_cell(&self, d: &DailyWeather, time_format: &TimeFormat) -> String {
+        let (temp_unit, speed_unit, _, precip_unit) = d.unit.symbols();
+        match self {
+            Column::Time => time_format.date(&d.date),
+            Column::Temp => format!("{:.1}..{:.1} {}", d.min_temp, d.max_temp, temp_unit),
+            // Daily forecasts carry neither a feels like temperature nor a wind direction.
+            Column::FeelsLike => "-".to_string(),
+            Column::Wind => format!("{:.1} {}", d.wind_speed, speed_unit),
+            Column::Precip => optional(d.precip, precip_unit),
+            Column::Condition => d.condition.clone(),
+            Column::Humidity => format!("{:.0}%", d.humidity),
+            Column::Clouds => d.clouds.map_or("-".to_string(), |c| format!("{:.0}%", c)),
+            Column::Uvi => format!("{:.1}", d.uvi),
+        }
+    }
+}
+
+/// Renders one row per hour, fitting the columns into `width` characters.
+pub fn hours(
+    hours: &[HourWeather],
+    columns: &[Column],
+    time_format: &TimeFormat,
+    width: usize,
+) -> String {
+    let rows = hours
+        .iter()
+        .map(|h| {
+            columns
+                .iter()
+                .map(|c| c.hour_cell(h, time_format))
+                .collect()
+        })
+        .collect();
+
+    render(columns, rows, width)
+}
+
+/// Renders one row per day, fitting the columns into `width` characters.
+pub fn days(
+    days: &[DailyWeather],
+    columns: &[Column],
+    time_format: &TimeFormat,
+    width: usize,
+) -> String {
+    let rows = days
+        .iter()
+        .map(|d| columns.iter().map(|c| c.day_cell(d, time_format)).collect())
+        .collect();
+
+    render(columns, rows, width)
+}
+
+fn optional(value: Option<f32>, unit: &str) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.1} {}", v, unit))
+}
+
+/// Aligns the cells, cutting the condition and then dropping the last columns
+/// until the table is no wider than `width`.
+fn render(columns: &[Column], rows: Vec<Vec<String>>, width: usize) -> String {
+    let header: Vec<String> = columns.iter().map(|c| c.title().to_string()).collect();
+    let mut widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .chain(std::iter::once(&header))
+                .map(|r| r[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let total =
+        |widths: &[usize]| widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+
+    if let Some(i) = columns.iter().position(|c| *c == Column::Condition) {
+        let excess = total(&widths).saturating_sub(width);
+        widths[i] = widths[i]
+            .saturating_sub(excess)
+            .max(MIN_CONDITION_WIDTH.min(widths[i]));
+    }
+    while widths.len() > 1 && total(&widths) > width {
+        widths.pop();
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cut(cell, width), width = width))
+            .collect::<Vec<String>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+fn cut(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+
+    let mut cut: String = cell.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
@@ -0,0 +1,431 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    errors::AppError,
+    weather::{
+        cardinal_dir_from, CurrentWeather, DailyWeather, ForecastTime, HourWeather, TimeFormat,
+        UnitType, Weather,
+    },
+    Result,
+};
+
+/// Fields of the current weather, see [`Fields`].
+const CURRENT_FIELDS: [&str; 20] = [
+    "clouds",
+    "condition",
+    "feels_like",
+    "humidity",
+    "precip",
+    "precip_unit",
+    "pressure",
+    "speed_unit",
+    "sunrise",
+    "sunset",
+    "temp",
+    "temp_unit",
+    "unit",
+    "units",
+    "uvi",
+    "vis_unit",
+    "visibility",
+    "wind_deg",
+    "wind_dir",
+    "wind_speed",
+];
+
+const HOUR_FIELDS: [&str; 19] = [
+    "clouds",
+    "condition",
+    "feels_like",
+    "humidity",
+    "precip",
+    "precip_unit",
+    "pressure",
+    "speed_unit",
+    "temp",
+    "temp_unit",
+    "time",
+    "unit",
+    "units",
+    "uvi",
+    "vis_unit",
+    "visibility",
+    "wind_deg",
+    "wind_dir",
+    "wind_speed",
+];
+
+const DAY_FIELDS: [&str; 26] = [
+    "avg_temp",
+    "clouds",
+    "condition",
+    "date",
+    "humidity",
+    "max",
+    "max_temp",
+    "min",
+    "min_temp",
+    "moon_phase",
+    "moonrise",
+    "moonset",
+    "precip",
+    "precip_unit",
+    "pressure",
+    "speed_unit",
+    "sunrise",
+    "sunset",
+    "temp",
+    "temp_unit",
+    "unit",
+    "units",
+    "uvi",
+    "vis_unit",
+    "visibility",
+    "wind_speed",
+];
+
+/// What a template is rendered for, each kind of entry has its own fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Current,
+    Hour,
+    Day,
+}
+
+impl EntryKind {
+    const ALL: [EntryKind; 3] = [EntryKind::Current, EntryKind::Hour, EntryKind::Day];
+
+    /// Names of the fields the entries have, sorted.
+    pub fn fields(self) -> &'static [&'static str] {
+        match self {
+            EntryKind::Current => &CURRENT_FIELDS,
+            EntryKind::Hour => &HOUR_FIELDS,
+            EntryKind::Day => &DAY_FIELDS,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            EntryKind::Current => "the current weather",
+            EntryKind::Hour => "hourly forecasts",
+            EntryKind::Day => "daily forecasts",
+        }
+    }
+}
+
+impl From<&ForecastTime> for EntryKind {
+    fn from(time: &ForecastTime) -> Self {
+        match time {
+            ForecastTime::Now => EntryKind::Current,
+            ForecastTime::Hours(_) => EntryKind::Hour,
+            ForecastTime::Days(_) => EntryKind::Day,
+        }
+    }
+}
+
+/// Output line with `{field}` or `{field:spec}` placeholders, `{{` and `}}` stand for braces.
+///
+/// The spec follows Rust's `format!`: an optional fill and alignment (`<`, `>`, `^`),
+/// a width and a precision, e.g. `{temp:>6.1}` or `{condition:.<20}`.
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Field { name: String, spec: Spec },
+}
+
+#[derive(Debug)]
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => {
+                                return Err(AppError::Template(format!(
+                                    "`{{{}` is not closed, write `{{{{` for a literal brace",
+                                    placeholder
+                                )))
+                            }
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(parse_field(&placeholder)?);
+                }
+                '}' => {
+                    return Err(AppError::Template(
+                        "unmatched `}`, write `}}` for a literal brace".to_string(),
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fails on fields the entries of the kind don't have, so it can be called
+    /// before anything is fetched.
+    pub fn check(&self, kind: EntryKind) -> Result<()> {
+        for part in &self.parts {
+            if let Part::Field { name, .. } = part {
+                if !kind.fields().contains(&name.as_str()) {
+                    return Err(AppError::Template(format!(
+                        "unknown field `{}` for {}, the available fields are: {}",
+                        name,
+                        kind.describe(),
+                        kind.fields().join(", ")
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders a line per hour or day, or a single one for the current weather.
+    pub fn lines(&self, weather: &Weather, time_format: &TimeFormat) -> Result<Vec<String>> {
+        match weather {
+            Weather::Current(current) => Ok(vec![self.render(current, time_format)?]),
+            Weather::Today(hours) => hours.iter().map(|h| self.render(h, time_format)).collect(),
+            Weather::Daily(days) => days.iter().map(|d| self.render(d, time_format)).collect(),
+        }
+    }
+
+    /// Fills the placeholders with the entry's fields, failing on a field it doesn't have.
+    pub fn render(&self, entry: &impl Fields, time_format: &TimeFormat) -> Result<String> {
+        let fields = entry.fields(time_format)?;
+        let mut out = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field { name, spec } => {
+                    let value = fields.get(name).ok_or_else(|| {
+                        AppError::Template(format!(
+                            "unknown field `{}`, the available fields are: {}",
+                            name,
+                            fields.keys().cloned().collect::<Vec<String>>().join(", ")
+                        ))
+                    })?;
+                    out.push_str(&spec.apply(value));
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+fn parse_field(placeholder: &str) -> Result<Part> {
+    let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AppError::Template(format!(
+            "`{{{}}}` doesn't name a field",
+            placeholder
+        )));
+    }
+    if !EntryKind::ALL.iter().any(|k| k.fields().contains(&name)) {
+        let mut known: Vec<&str> = EntryKind::ALL
+            .iter()
+            .flat_map(|k| k.fields())
+            .copied()
+            .collect();
+        known.sort();
+        known.dedup();
+        return Err(AppError::Template(format!(
+            "unknown field `{}`, the available fields are: {}",
+            name,
+            known.join(", ")
+        )));
+    }
+
+    let invalid = || {
+        AppError::Template(format!(
+            "invalid format spec `{}` of field `{}`",
+            spec, name
+        ))
+    };
+    let align = |c| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+
+    let chars: Vec<char> = spec.chars().collect();
+    let (fill, align, rest) = match chars.as_slice() {
+        [fill, a, rest @ ..] if align(*a).is_some() => (*fill, align(*a), rest),
+        [a, rest @ ..] if align(*a).is_some() => (' ', align(*a), rest),
+        rest => (' ', None, rest),
+    };
+    let rest: String = rest.iter().collect();
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision.parse().map_err(|_| invalid())?)),
+        None => (rest.as_str(), None),
+    };
+    let width = match width {
+        "" => 0,
+        width => width.parse().map_err(|_| invalid())?,
+    };
+
+    Ok(Part::Field {
+        name: name.to_string(),
+        spec: Spec {
+            fill,
+            align,
+            width,
+            precision,
+        },
+    })
+}
+
+impl Spec {
+    fn apply(&self, value: &Value) -> String {
+        let (text, numeric) = match value {
+            Value::Number(n) => {
+                let text = match (self.precision, n.as_i64()) {
+                    (Some(precision), _) => {
+                        format!("{:.*}", precision, n.as_f64().unwrap_or_default())
+                    }
+                    (None, Some(n)) => n.to_string(),
+                    // Model values are f32, printed as such they don't carry conversion noise.
+                    (None, None) => (n.as_f64().unwrap_or_default() as f32).to_string(),
+                };
+                (text, true)
+            }
+            Value::String(s) => match self.precision {
+                Some(precision) => (s.chars().take(precision).collect(), false),
+                None => (s.clone(), false),
+            },
+            Value::Null => ("-".to_string(), false),
+            other => (other.to_string(), false),
+        };
+
+        let padding = self.width.saturating_sub(text.chars().count());
+        let align = self
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = |n| self.fill.to_string().repeat(n);
+
+        format!("{}{}{}", fill(before), text, fill(after))
+    }
+}
+
+/// Forecast entry whose fields templates can reference.
+pub trait Fields {
+    /// Every field of the model, times in the given format, plus derived ones like units.
+    fn fields(&self, time_format: &TimeFormat) -> Result<BTreeMap<String, Value>>;
+}
+
+impl Fields for CurrentWeather {
+    fn fields(&self, time_format: &TimeFormat) -> Result<BTreeMap<String, Value>> {
+        let mut fields = model_fields(self, &self.unit)?;
+        fields.insert("sunrise".into(), time(&self.sunrise, time_format));
+        fields.insert("sunset".into(), time(&self.sunset, time_format));
+        fields.insert("wind_dir".into(), cardinal_dir_from(self.wind_deg).into());
+
+        Ok(fields)
+    }
+}
+
+impl Fields for HourWeather {
+    fn fields(&self, time_format: &TimeFormat) -> Result<BTreeMap<String, Value>> {
+        let mut fields = model_fields(self, &self.unit)?;
+        fields.insert("time".into(), time_format.datetime(&self.time).into());
+        fields.insert("wind_dir".into(), cardinal_dir_from(self.wind_deg).into());
+
+        Ok(fields)
+    }
+}
+
+impl Fields for DailyWeather {
+    fn fields(&self, time_format: &TimeFormat) -> Result<BTreeMap<String, Value>> {
+        let mut fields = model_fields(self, &self.unit)?;
+        fields.insert("date".into(), time_format.date(&self.date).into());
+        fields.insert("sunrise".into(), time(&self.sunrise, time_format));
+        fields.insert("sunset".into(), time(&self.sunset, time_format));
+        fields.insert("moonrise".into(), time(&self.moonrise, time_format));
+        fields.insert("moonset".into(), time(&self.moonset, time_format));
+        // Short names for one-line summaries.
+        fields.insert("temp".into(), self.avg_temp.into());
+        fields.insert("min".into(), self.min_temp.into());
+        fields.insert("max".into(), self.max_temp.into());
+
+        Ok(fields)
+    }
+}
+
+/// Serialized fields of the entry, `unit` being the temperature scale and `units` the system.
+fn model_fields(entry: &impl Serialize, unit: &UnitType) -> Result<BTreeMap<String, Value>> {
+    let Value::Object(map) = serde_json::to_value(entry)? else {
+        unreachable!("forecast entries are serialized as objects")
+    };
+    let mut fields: BTreeMap<String, Value> = map.into_iter().collect();
+
+    let (temp_unit, speed_unit, vis_unit, precip_unit) = unit.symbols();
+    if let Some(units) = fields.remove("unit") {
+        fields.insert("units".into(), units);
+    }
+    fields.insert("unit".into(), temp_unit.trim_start_matches('°').into());
+    fields.insert("temp_unit".into(), temp_unit.into());
+    fields.insert("speed_unit".into(), speed_unit.into());
+    fields.insert("vis_unit".into(), vis_unit.into());
+    fields.insert("precip_unit".into(), precip_unit.into());
+
+    Ok(fields)
+}
+
+fn time(time: &Option<DateTime<FixedOffset>>, time_format: &TimeFormat) -> Value {
+    time.map_or(Value::Null, |t| time_format.time(&t).into())
+}
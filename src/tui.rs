@@ -0,0 +1,421 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{stdout, Stdout},
+    sync::Arc,
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    prelude::*,
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Cell, Chart, Dataset, GraphType, List,
+        ListItem, ListState, Paragraph, Row, Table, Wrap,
+    },
+};
+
+use tokio::sync::mpsc;
+use weather::{
+    errors::AppError,
+    providers::chain::ProviderChain,
+    weather::{
+        cardinal_dir_from, CurrentWeather, DailyWeather, ForecastTime, HourWeather, Place,
+        TimeFormat, UnitType, Weather,
+    },
+    Result,
+};
+
+const HOURS: usize = 24;
+const DAYS: usize = 7;
+
+/// How long to sleep when no key was pressed, fetches go on meanwhile.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Terminal switched to the alternate screen in raw mode, restored when dropped.
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        Ok(Screen { terminal })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Current conditions, hourly and daily forecasts of a single place.
+struct Forecasts {
+    provider: Option<String>,
+    current: Result<CurrentWeather>,
+    hours: Result<Vec<HourWeather>>,
+    days: Result<Vec<DailyWeather>>,
+}
+
+impl Forecasts {
+    async fn fetch(chain: &ProviderChain, place: &Place, unit: &UnitType) -> Self {
+        let get = |time| chain.get_forecast(place.coordinates.clone(), time, unit.clone());
+        let (current, hours, days) = futures::join!(
+            get(ForecastTime::Now),
+            get(ForecastTime::Hours(HOURS)),
+            get(ForecastTime::Days(DAYS))
+        );
+
+        let provider = [&current, &hours, &days]
+            .into_iter()
+            .find_map(|r| r.as_ref().ok().map(|r| r.provider.clone()));
+
+        Forecasts {
+            provider,
+            current: current.map(|r| match r.forecast.weather {
+                Weather::Current(current) => current,
+                _ => unreachable!("the current weather was requested"),
+            }),
+            hours: hours.map(|r| match r.forecast.weather {
+                Weather::Today(hours) => hours,
+                _ => unreachable!("an hourly forecast was requested"),
+            }),
+            days: days.map(|r| match r.forecast.weather {
+                Weather::Daily(days) => days,
+                _ => unreachable!("a daily forecast was requested"),
+            }),
+        }
+    }
+}
+
+struct Dashboard {
+    places: Vec<Place>,
+    selected: ListState,
+    forecasts: HashMap<String, Forecasts>,
+    /// Places whose forecasts are being fetched.
+    loading: HashSet<String>,
+    unit: UnitType,
+    time_format: TimeFormat,
+}
+
+impl Dashboard {
+    fn place(&self) -> &Place {
+        &self.places[self.selected.selected().unwrap_or_default()]
+    }
+
+    fn select(&mut self, step: isize) {
+        let count = self.places.len() as isize;
+        let current = self.selected.selected().unwrap_or_default() as isize;
+        self.selected
+            .select(Some((current + step).rem_euclid(count) as usize));
+    }
+}
+
+/// Shows the forecasts of the saved places until the user quits.
+pub async fn run(
+    chain: Arc<ProviderChain>,
+    mut places: Vec<Place>,
+    unit: UnitType,
+    time_format: TimeFormat,
+) -> Result<()> {
+    places.sort_by(|a, b| a.tag.tag.cmp(&b.tag.tag));
+    let mut app = Dashboard {
+        places,
+        selected: ListState::default().with_selected(Some(0)),
+        forecasts: HashMap::new(),
+        loading: HashSet::new(),
+        unit,
+        time_format,
+    };
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut screen = Screen::enter()?;
+    loop {
+        while let Ok((tag, forecasts)) = receiver.try_recv() {
+            app.loading.remove(&tag);
+            app.forecasts.insert(tag, forecasts);
+        }
+
+        // Fetched in the background, so keys keep working while the loading screen is up.
+        let place = app.place().clone();
+        if !app.forecasts.contains_key(&place.tag.tag) && app.loading.insert(place.tag.tag.clone())
+        {
+            let (chain, sender, unit) = (chain.clone(), sender.clone(), app.unit.clone());
+            tokio::spawn(async move {
+                let forecasts = Forecasts::fetch(&chain, &place, &unit).await;
+                let _ = sender.send((place.tag.tag, forecasts));
+            });
+        }
+
+        screen.terminal.draw(|f| draw(f, &mut app))?;
+
+        // Blocking on the terminal would stall the runtime, so poll and sleep instead.
+        if !event::poll(Duration::ZERO)? {
+            tokio::time::sleep(TICK).await;
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => app.select(-1),
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => app.select(1),
+                KeyCode::Char('r') => {
+                    let tag = app.place().tag.tag.clone();
+                    app.forecasts.remove(&tag);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(f: &mut Frame, app: &mut Dashboard) {
+    let [sidebar, main] = split(
+        Direction::Horizontal,
+        f.size(),
+        [Constraint::Length(24), Constraint::Min(40)],
+    );
+
+    let items: Vec<ListItem> = app
+        .places
+        .iter()
+        .map(|p| ListItem::new(p.tag.tag.clone()))
+        .collect();
+    let list = List::new(items)
+        .block(panel("Places"))
+        .highlight_style(Style::new().reversed())
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, sidebar, &mut app.selected);
+
+    let place = app.place();
+    let Some(forecasts) = app.forecasts.get(&place.tag.tag) else {
+        let loading = Paragraph::new(format!("Loading the forecast for {}...", place.tag.tag));
+        f.render_widget(loading.block(panel(&place.tag.tag)), main);
+        return;
+    };
+
+    let [current, hours, days, help] = split(
+        Direction::Vertical,
+        main,
+        [
+            Constraint::Length(8),
+            Constraint::Min(12),
+            Constraint::Length(DAYS as u16 + 3),
+            Constraint::Length(1),
+        ],
+    );
+
+    let title = match &forecasts.provider {
+        Some(provider) => format!("{} - {}", place.tag.tag, provider),
+        None => place.tag.tag.clone(),
+    };
+    match &forecasts.current {
+        Ok(weather) => draw_current(f, current, &title, weather, &app.time_format),
+        Err(e) => draw_error(f, current, &title, e),
+    }
+    match &forecasts.hours {
+        Ok(weather) => draw_hours(f, hours, weather, &app.unit, &app.time_format),
+        Err(e) => draw_error(f, hours, "Next 24 hours", e),
+    }
+    match &forecasts.days {
+        Ok(weather) => draw_days(f, days, weather, &app.unit, &app.time_format),
+        Err(e) => draw_error(f, days, "Next days", e),
+    }
+
+    let keys = "Up/Down switch places  r reload  q quit";
+    f.render_widget(Paragraph::new(keys).dim(), help);
+}
+
+fn draw_current(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    weather: &CurrentWeather,
+    time_format: &TimeFormat,
+) {
+    let (temp_unit, speed_unit, _, precip_unit) = weather.unit.symbols();
+    let mut lines = vec![
+        Line::from(weather.condition.clone().bold()),
+        Line::from(format!(
+            "Temperature: {:.1} {}, feels like {:.1} {}",
+            weather.temp, temp_unit, weather.feels_like, temp_unit
+        )),
+        Line::from(format!(
+            "Humidity: {} %, clouds: {} %",
+            weather.humidity, weather.clouds
+        )),
+        Line::from(format!(
+            "Wind: {:.1} {}, {}",
+            weather.wind_speed,
+            speed_unit,
+            cardinal_dir_from(weather.wind_deg)
+        )),
+    ];
+    if let Some(precip) = weather.precip {
+        lines.push(Line::from(format!(
+            "Precipitation: {:.1} {}",
+            precip, precip_unit
+        )));
+    }
+    if let (Some(sunrise), Some(sunset)) = (weather.sunrise, weather.sunset) {
+        lines.push(Line::from(format!(
+            "Sunrise: {}, sunset: {}",
+            time_format.time(&sunrise),
+            time_format.time(&sunset)
+        )));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(panel(title)), area);
+}
+
+fn draw_hours(
+    f: &mut Frame,
+    area: Rect,
+    hours: &[HourWeather],
+    unit: &UnitType,
+    time_format: &TimeFormat,
+) {
+    let (temp_unit, _, _, precip_unit) = unit.symbols();
+    let [temp_area, precip_area] = split(
+        Direction::Vertical,
+        area,
+        [Constraint::Percentage(65), Constraint::Percentage(35)],
+    );
+
+    let temps: Vec<(f64, f64)> = hours
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (i as f64, h.temp as f64))
+        .collect();
+    let min = temps.iter().map(|t| t.1).fold(f64::INFINITY, f64::min);
+    let max = temps.iter().map(|t| t.1).fold(f64::NEG_INFINITY, f64::max);
+    let (min, max) = if min <= max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (0.0, 1.0)
+    };
+
+    let x_labels = match (hours.first(), hours.get(hours.len() / 2), hours.last()) {
+        (Some(first), Some(middle), Some(last)) => [first, middle, last]
+            .into_iter()
+            .map(|h| Span::raw(time_format.time(&h.time)))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let dataset = Dataset::default()
+        .name(temp_unit)
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::new().yellow())
+        .data(&temps);
+    let chart = Chart::new(vec![dataset])
+        .block(panel(&format!("Next {} hours, temperature", hours.len())))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, temps.len().saturating_sub(1) as f64])
+                .labels(x_labels),
+        )
+        .y_axis(Axis::default().bounds([min, max]).labels(vec![
+            Span::raw(format!("{:.0}", min)),
+            Span::raw(format!("{:.0}", max)),
+        ]));
+    f.render_widget(chart, temp_area);
+
+    let precip: Vec<f32> = hours.iter().map(|h| h.precip.unwrap_or_default()).collect();
+    let most = precip.iter().copied().fold(0.0, f32::max);
+    // Bars hold integers, so the amounts are drawn in hundredths.
+    let bars: Vec<Bar> = precip
+        .iter()
+        .map(|p| {
+            Bar::default()
+                .value((p * 100.0).round() as u64)
+                .text_value(String::new())
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(panel(&format!(
+            "Precipitation, up to {:.1} {}",
+            most, precip_unit
+        )))
+        .bar_width(2)
+        .bar_gap(1)
+        .bar_style(Style::new().blue())
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, precip_area);
+}
+
+fn draw_days(
+    f: &mut Frame,
+    area: Rect,
+    days: &[DailyWeather],
+    unit: &UnitType,
+    time_format: &TimeFormat,
+) {
+    let (temp_unit, _, _, precip_unit) = unit.symbols();
+    let header = Row::new(["Date", "Condition", "Min", "Max", "Precipitation"]).bold();
+    let rows = days.iter().map(|d| {
+        Row::new([
+            Cell::from(time_format.date(&d.date)),
+            Cell::from(d.condition.clone()),
+            Cell::from(format!("{:.1} {}", d.min_temp, temp_unit)),
+            Cell::from(format!("{:.1} {}", d.max_temp, temp_unit)),
+            Cell::from(
+                d.precip
+                    .map(|p| format!("{:.1} {}", p, precip_unit))
+                    .unwrap_or_default(),
+            ),
+        ])
+    });
+
+    let table = Table::new(rows)
+        .header(header)
+        .block(panel(&format!("Next {} days", days.len())))
+        .widths(&[
+            Constraint::Length(12),
+            Constraint::Min(14),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(14),
+        ]);
+    f.render_widget(table, area);
+}
+
+fn draw_error(f: &mut Frame, area: Rect, title: &str, error: &AppError) {
+    let text = Paragraph::new(error.to_string().trim_end().to_string())
+        .red()
+        .wrap(Wrap { trim: true })
+        .block(panel(title));
+    f.render_widget(text, area);
+}
+
+fn panel(title: &str) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string())
+}
+
+fn split<const N: usize>(
+    direction: Direction,
+    area: Rect,
+    constraints: [Constraint; N],
+) -> [Rect; N] {
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area);
+
+    std::array::from_fn(|i| chunks[i])
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use crate::{
+    compare::precip_threshold,
+    weather::{TimeFormat, UnitType, Weather},
+};
+
+/// Temperature changes smaller than this are not worth reporting.
+const TEMP_CHANGE: f32 = 0.1;
+
+/// Longest pause between refreshes while the providers keep failing.
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+/// Delay before the next refresh, doubled after every failed one in a row.
+pub fn next_delay(interval: Duration, failures: u32) -> Duration {
+    interval
+        .saturating_mul(2u32.saturating_pow(failures))
+        .min(MAX_BACKOFF.max(interval))
+}
+
+/// Describes how the forecast changed since the previous refresh, hours and
+/// days are matched by their time so a shifted window is not reported.
+pub fn changes(prev: &Weather, next: &Weather, time_format: &TimeFormat) -> Vec<String> {
+    match (prev, next) {
+        (Weather::Current(p), Weather::Current(n)) => describe(
+            "Now",
+            Reading::new("temperature", p.temp, p.precip, &p.unit),
+            Reading::new("temperature", n.temp, n.precip, &n.unit),
+            &n.unit,
+        ),
+        (Weather::Today(p), Weather::Today(n)) => n
+            .iter()
+            .filter_map(|h| p.iter().find(|ph| ph.time == h.time).map(|ph| (ph, h)))
+            .flat_map(|(ph, h)| {
+                describe(
+                    &time_format.datetime(&h.time),
+                    Reading::new("temperature", ph.temp, ph.precip, &ph.unit),
+                    Reading::new("temperature", h.temp, h.precip, &h.unit),
+                    &h.unit,
+                )
+            })
+            .collect(),
+        (Weather::Daily(p), Weather::Daily(n)) => n
+            .iter()
+            .filter_map(|d| p.iter().find(|pd| pd.date == d.date).map(|pd| (pd, d)))
+            .flat_map(|(pd, d)| {
+                describe(
+                    &time_format.date(&d.date),
+                    Reading::new("max temperature", pd.max_temp, pd.precip, &pd.unit),
+                    Reading::new("max temperature", d.max_temp, d.precip, &d.unit),
+                    &d.unit,
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+struct Reading {
+    label: &'static str,
+    temp: f32,
+    precip: Option<f32>,
+    unit: UnitType,
+}
+
+impl Reading {
+    fn new(label: &'static str, temp: f32, precip: Option<f32>, unit: &UnitType) -> Self {
+        Reading {
+            label,
+            temp,
+            precip,
+            unit: unit.clone(),
+        }
+    }
+
+    fn wet(&self) -> bool {
+        self.precip
+            .is_some_and(|p| p >= precip_threshold(&self.unit))
+    }
+}
+
+fn describe(when: &str, prev: Reading, next: Reading, unit: &UnitType) -> Vec<String> {
+    let (temp_unit, _, _, precip_unit) = unit.symbols();
+    let mut out = Vec::new();
+
+    let delta = next.temp - prev.temp;
+    if delta.abs() >= TEMP_CHANGE {
+        out.push(format!(
+            "{}: {} {:+.1} {}, now {:.1} {}",
+            when, next.label, delta, temp_unit, next.temp, temp_unit
+        ));
+    }
+
+    match (prev.wet(), next.wet()) {
+        (false, true) => out.push(format!(
+            "{}: precipitation started, {:.1} {}",
+            when,
+            next.precip.unwrap_or_default(),
+            precip_unit
+        )),
+        (true, false) => out.push(format!("{}: precipitation stopped", when)),
+        _ => {}
+    }
+
+    out
+}
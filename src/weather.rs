@@ -1,18 +1,105 @@
 use core::fmt;
-use std::hash::Hash;
-
-use crate::Result;
-use clap::{Args, Subcommand, ValueEnum};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+use crate::{secret, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use clap::{value_parser, Args, Subcommand, ValueEnum};
 use serde_derive::{Deserialize, Serialize};
 
+/// Forecast for the requested period, the same for every provider.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
 pub enum Weather {
     Current(CurrentWeather),
+    #[serde(rename = "hourly")]
     Today(Vec<HourWeather>),
     Daily(Vec<DailyWeather>),
 }
-#[derive(Debug)]
+
+/// How times and dates are displayed.
+#[derive(ValueEnum, Deserialize, Serialize, Clone, Copy, Default, Debug)]
+pub enum TimeFormat {
+    /// 24-hour clock, e.g. 18:30
+    #[default]
+    #[value(name = "24h")]
+    #[serde(rename = "24h")]
+    H24,
+
+    /// 12-hour clock, e.g. 06:30 PM
+    #[value(name = "12h")]
+    #[serde(rename = "12h")]
+    H12,
+
+    /// ISO 8601 timestamps with the UTC offset, e.g. 2023-11-01T18:30:00+02:00
+    Iso,
+}
+
+impl TimeFormat {
+    /// Date and time of the day.
+    pub fn datetime(&self, dt: &DateTime<FixedOffset>) -> String {
+        match self {
+            TimeFormat::H24 => dt.format("%Y-%m-%d %H:%M").to_string(),
+            TimeFormat::H12 => dt.format("%Y-%m-%d %I:%M %p").to_string(),
+            TimeFormat::Iso => dt.to_rfc3339(),
+        }
+    }
+
+    /// Time of the day only, the date is known from the context.
+    pub fn time(&self, dt: &DateTime<FixedOffset>) -> String {
+        match self {
+            TimeFormat::H24 => dt.format("%H:%M").to_string(),
+            TimeFormat::H12 => dt.format("%I:%M %p").to_string(),
+            TimeFormat::Iso => dt.to_rfc3339(),
+        }
+    }
+
+    /// Sample of the format for confirmation messages.
+    pub fn example(&self) -> &'static str {
+        match self {
+            TimeFormat::H24 => "18:30",
+            TimeFormat::H12 => "06:30 PM",
+            TimeFormat::Iso => "2023-11-01T18:30:00+02:00",
+        }
+    }
+
+    /// Date of a daily forecast, always ISO like "2023-11-01" whatever the format,
+    /// taken here so that callers format every kind of entry the same way.
+    pub fn date(&self, date: &NaiveDate) -> String {
+        date.format("%Y-%m-%d").to_string()
+    }
+}
+
+/// Forecast parts whose timestamps can be displayed in the chosen format.
+pub trait TimeFormatted: Sized {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result;
+
+    fn formatted(&self, time_format: TimeFormat) -> Formatted<'_, Self> {
+        Formatted {
+            value: self,
+            time_format,
+        }
+    }
+}
+
+pub struct Formatted<'a, T> {
+    value: &'a T,
+    time_format: TimeFormat,
+}
+
+impl<T: TimeFormatted> fmt::Display for Formatted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.write_to(f, &self.time_format)
+    }
+}
+
+/// Forecast for a single day, times are in the local time of the location.
+#[derive(Deserialize, Serialize, Debug)]
 pub struct DailyWeather {
-    pub date: String,
+    pub date: NaiveDate,
     pub min_temp: f32,
     pub max_temp: f32,
     pub avg_temp: Option<f32>,
@@ -24,22 +111,22 @@ pub struct DailyWeather {
     pub clouds: Option<f32>,
     pub condition: String,
     pub precip: Option<f32>,
-    pub sunrise: Option<String>,
-    pub sunset: Option<String>,
-    pub moonrise: Option<String>,
-    pub moonset: Option<String>,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
+    pub moonrise: Option<DateTime<FixedOffset>>,
+    pub moonset: Option<DateTime<FixedOffset>>,
     pub moon_phase: Option<String>,
     pub unit: UnitType,
 }
 
-impl fmt::Display for DailyWeather {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl TimeFormatted for DailyWeather {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result {
         let (temp_unit, speed_unit, vis_unit) = match self.unit {
             UnitType::Metric => ("°C", "m/s", "km"),
             UnitType::Imperial => ("°F", "mph", "mi"),
         };
 
-        writeln!(f, "Daily Weather for {}:", self.date)?;
+        writeln!(f, "Daily Weather for {}:", time_format.date(&self.date))?;
         writeln!(f, "Min Temp: {:.2}{}", self.min_temp, temp_unit)?;
         writeln!(f, "Max Temp: {:.2}{}", self.max_temp, temp_unit)?;
 
@@ -71,19 +158,19 @@ impl fmt::Display for DailyWeather {
         }
 
         if let Some(sunrise) = &self.sunrise {
-            writeln!(f, "Sunrise: {}", sunrise)?;
+            writeln!(f, "Sunrise: {}", time_format.time(sunrise))?;
         }
 
         if let Some(sunset) = &self.sunset {
-            writeln!(f, "Sunset: {}", sunset)?;
+            writeln!(f, "Sunset: {}", time_format.time(sunset))?;
         }
 
         if let Some(moonrise) = &self.moonrise {
-            writeln!(f, "Moonrise: {}", moonrise)?;
+            writeln!(f, "Moonrise: {}", time_format.time(moonrise))?;
         }
 
         if let Some(moonset) = &self.moonset {
-            writeln!(f, "Moonset: {}", moonset)?;
+            writeln!(f, "Moonset: {}", time_format.time(moonset))?;
         }
 
         if let Some(moon_phase) = self.moon_phase.as_deref() {
@@ -94,9 +181,16 @@ impl fmt::Display for DailyWeather {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for DailyWeather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f, &TimeFormat::default())
+    }
+}
+
+/// Forecast for a single hour.
+#[derive(Deserialize, Serialize, Debug)]
 pub struct HourWeather {
-    pub time: String,
+    pub time: DateTime<FixedOffset>,
     pub temp: f32,
     pub feels_like: f32,
     pub visibility: f32,
@@ -111,14 +205,14 @@ pub struct HourWeather {
     pub unit: UnitType,
 }
 
-impl fmt::Display for HourWeather {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl TimeFormatted for HourWeather {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result {
         let (temp_unit, speed_unit, vis_unit) = match self.unit {
             UnitType::Metric => ("°C", "m/s", "km"),
             UnitType::Imperial => ("°F", "mph", "mi"),
         };
 
-        writeln!(f, "Time: {}", self.time)?;
+        writeln!(f, "Time: {}", time_format.datetime(&self.time))?;
         writeln!(f, "Temperature: {:.2} {}", self.temp, temp_unit)?;
         writeln!(f, "Feels Like: {:.2} {}", self.feels_like, temp_unit)?;
         writeln!(f, "Visibility: {:.2} {}", self.visibility, vis_unit)?;
@@ -142,7 +236,14 @@ impl fmt::Display for HourWeather {
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for HourWeather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f, &TimeFormat::default())
+    }
+}
+
+/// Weather conditions at the moment of the request.
+#[derive(Deserialize, Serialize, Debug)]
 pub struct CurrentWeather {
     pub temp: f32,
     pub feels_like: f32,
@@ -153,15 +254,15 @@ pub struct CurrentWeather {
     pub wind_speed: f32,
     pub wind_deg: f32,
     pub uvi: f32,
-    pub sunrise: Option<String>,
-    pub sunset: Option<String>,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
     pub condition: String,
     pub precip: Option<f32>,
     pub unit: UnitType,
 }
 
-impl fmt::Display for CurrentWeather {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl TimeFormatted for CurrentWeather {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result {
         let (temp_unit, speed_unit, vis_unit) = match self.unit {
             UnitType::Metric => ("°C", "m/s", "km"),
             UnitType::Imperial => ("°F", "mph", "mi"),
@@ -179,11 +280,11 @@ impl fmt::Display for CurrentWeather {
         writeln!(f, "UV Index: {:.2}", self.uvi)?;
 
         if let Some(sunrise) = &self.sunrise {
-            writeln!(f, "Sunrise: {}", sunrise)?;
+            writeln!(f, "Sunrise: {}", time_format.time(sunrise))?;
         }
 
         if let Some(sunset) = &self.sunset {
-            writeln!(f, "Sunset: {}", sunset)?;
+            writeln!(f, "Sunset: {}", time_format.time(sunset))?;
         }
 
         writeln!(f, "Condition: {}", self.condition)?;
@@ -200,10 +301,163 @@ impl fmt::Display for CurrentWeather {
     }
 }
 
-#[derive(Deserialize, Serialize, Subcommand, Clone, Debug)]
+impl fmt::Display for CurrentWeather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f, &TimeFormat::default())
+    }
+}
+
+/// Severe weather warning issued for the location.
+#[derive(Serialize, Debug)]
+pub struct WeatherAlert {
+    pub sender: Option<String>,
+    pub event: String,
+    pub severity: Option<String>,
+    pub start: Option<DateTime<FixedOffset>>,
+    pub end: Option<DateTime<FixedOffset>>,
+    pub description: String,
+}
+
+impl TimeFormatted for WeatherAlert {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result {
+        writeln!(f, "Alert: {}", self.event)?;
+
+        if let Some(severity) = &self.severity {
+            writeln!(f, "Severity: {}", severity)?;
+        }
+
+        if let Some(sender) = &self.sender {
+            writeln!(f, "Issued by: {}", sender)?;
+        }
+
+        if let Some(start) = &self.start {
+            writeln!(f, "Start: {}", time_format.datetime(start))?;
+        }
+
+        if let Some(end) = &self.end {
+            writeln!(f, "End: {}", time_format.datetime(end))?;
+        }
+
+        writeln!(f, "Description: {}", self.description.trim())
+    }
+}
+
+impl fmt::Display for WeatherAlert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f, &TimeFormat::default())
+    }
+}
+
+/// Air quality now or for the following hours.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum AirForecast {
+    Current(AirQuality),
+    Hourly(Vec<AirQuality>),
+}
+
+/// Air quality index together with the pollutant concentrations in μg/m³.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AirQuality {
+    /// In UTC when the provider doesn't tell the time zone of the location
+    pub time: DateTime<FixedOffset>,
+    pub aqi: u8,
+    pub scale: AqiScale,
+    pub co: f32,
+    pub no2: f32,
+    pub o3: f32,
+    pub so2: f32,
+    pub pm2_5: f32,
+    pub pm10: f32,
+}
+
+/// Scale the air quality index is expressed in, providers use different ones.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum AqiScale {
+    /// 1 (good) to 6 (hazardous)
+    UsEpa,
+    /// OpenWeather's own, 1 (good) to 5 (very poor)
+    OpenWeather,
+}
+
+impl AqiScale {
+    pub fn max(&self) -> u8 {
+        match self {
+            AqiScale::UsEpa => 6,
+            AqiScale::OpenWeather => 5,
+        }
+    }
+
+    /// Name of the band the index falls into, e.g. "Moderate".
+    pub fn band(&self, aqi: u8) -> &'static str {
+        match (self, aqi) {
+            (AqiScale::UsEpa, 1) => "Good",
+            (AqiScale::UsEpa, 2) => "Moderate",
+            (AqiScale::UsEpa, 3) => "Unhealthy for sensitive groups",
+            (AqiScale::UsEpa, 4) => "Unhealthy",
+            (AqiScale::UsEpa, 5) => "Very unhealthy",
+            (AqiScale::UsEpa, 6) => "Hazardous",
+            (AqiScale::OpenWeather, 1) => "Good",
+            (AqiScale::OpenWeather, 2) => "Fair",
+            (AqiScale::OpenWeather, 3) => "Moderate",
+            (AqiScale::OpenWeather, 4) => "Poor",
+            (AqiScale::OpenWeather, 5) => "Very poor",
+            _ => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for AqiScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AqiScale::UsEpa => write!(f, "US EPA"),
+            AqiScale::OpenWeather => write!(f, "OpenWeather"),
+        }
+    }
+}
+
+impl TimeFormatted for AirQuality {
+    fn write_to(&self, f: &mut fmt::Formatter, time_format: &TimeFormat) -> fmt::Result {
+        writeln!(f, "Time: {}", time_format.datetime(&self.time))?;
+        writeln!(
+            f,
+            "Air Quality Index: {} of {}, {} ({})",
+            self.aqi,
+            self.scale.max(),
+            self.scale.band(self.aqi),
+            self.scale
+        )?;
+        writeln!(f, "CO: {:.2} μg/m³", self.co)?;
+        writeln!(f, "NO2: {:.2} μg/m³", self.no2)?;
+        writeln!(f, "O3: {:.2} μg/m³", self.o3)?;
+        writeln!(f, "SO2: {:.2} μg/m³", self.so2)?;
+        writeln!(f, "PM2.5: {:.2} μg/m³", self.pm2_5)?;
+        writeln!(f, "PM10: {:.2} μg/m³", self.pm10)
+    }
+}
+
+impl fmt::Display for AirQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f, &TimeFormat::default())
+    }
+}
+
+/// Precipitation expected within a single minute of the nowcast.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MinutePrecip {
+    pub time: DateTime<FixedOffset>,
+    /// Intensity in mm/h whatever the unit system is
+    pub precip: f32,
+}
+
+#[derive(Deserialize, Serialize, Subcommand, PartialEq, Clone, Debug)]
 pub enum Provider {
     OpenWeather(ProviderCredentials),
     WeatherApi(ProviderCredentials),
+    OpenMeteo,
+    /// Replies recorded with `forecast --record`, parsed by the recorded provider
+    Replay(ReplaySource),
 }
 
 impl fmt::Display for Provider {
@@ -211,14 +465,114 @@ impl fmt::Display for Provider {
         match self {
             Provider::OpenWeather(_) => write!(f, "Open Weather"),
             Provider::WeatherApi(_) => write!(f, "Weather API"),
+            Provider::OpenMeteo => write!(f, "Open Meteo"),
+            Provider::Replay(_) => write!(f, "Replay"),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Args, Clone, Debug)]
+impl Provider {
+    /// Names the configured provider in cache file names, e.g. "open-weather-1f2e3d4c",
+    /// so providers of the same kind with different credentials keep apart.
+    pub fn cache_id(&self) -> String {
+        let name = self.to_string().to_lowercase().replace(' ', "-");
+        match self {
+            Provider::OpenWeather(creds) | Provider::WeatherApi(creds) => {
+                let mut hasher = DefaultHasher::new();
+                creds.hash(&mut hasher);
+                format!("{}-{:08x}", name, hasher.finish() as u32)
+            }
+            Provider::OpenMeteo | Provider::Replay(_) => name,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Args, PartialEq, Clone, Debug)]
+pub struct ReplaySource {
+    /// Directory with the recorded replies
+    #[arg(long)]
+    pub dir: PathBuf,
+}
+
+/// Where the API key comes from, exactly one of the sources is set.
+#[derive(Deserialize, Serialize, Args, PartialEq, Hash, Clone, Debug)]
+#[group(required = true, multiple = false)]
 pub struct ProviderCredentials {
+    /// API key, stored in the config file as plain text
     #[arg(short, long)]
-    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "present")]
+    pub key: Option<String>,
+
+    /// Environment variable holding the API key
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "present")]
+    pub key_env: Option<String>,
+
+    /// Command printing the API key, e.g. "pass show weather/api-key"
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "present")]
+    pub key_command: Option<String>,
+
+    /// File holding the API key, readable by its owner only
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "present")]
+    pub key_file: Option<PathBuf>,
+}
+
+/// Stores the set sources as plain values, so configs written before the other
+/// sources existed, with a bare `key: "..."`, still load.
+mod present {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        T::deserialize(deserializer).map(Some)
+    }
+}
+
+impl ProviderCredentials {
+    /// Reads the API key from its source.
+    pub fn resolve_key(&self) -> Result<String> {
+        match (&self.key, &self.key_env, &self.key_command, &self.key_file) {
+            (Some(key), _, _, _) => Ok(key.to_owned()),
+            (_, Some(var), _, _) => secret::from_env(var),
+            (_, _, Some(command), _) => secret::from_command(command),
+            (_, _, _, Some(path)) => secret::from_file(path),
+            _ => Err(crate::errors::AppError::Secret(
+                "no API key source is configured".to_string(),
+            )),
+        }
+    }
+
+    /// Describes the key source for listings, a plain text key is masked.
+    pub fn describe(&self) -> String {
+        match (&self.key, &self.key_env, &self.key_command, &self.key_file) {
+            (Some(key), _, _, _) => {
+                let visible = key.chars().count().saturating_sub(4);
+                let masked = key
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| if i < visible { '*' } else { c })
+                    .collect::<String>();
+                format!("key {}", masked)
+            }
+            (_, Some(var), _, _) => format!("key from ${}", var),
+            (_, _, Some(command), _) => format!("key from `{}`", command),
+            (_, _, _, Some(path)) => format!("key from {}", path.display()),
+            _ => "no key".to_string(),
+        }
+    }
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -227,7 +581,7 @@ pub enum PlacesAction {
     GetAll,
 
     /// Save the new place or update the location of existed place by tag
-    Set(Place),
+    Set(SetPlace),
 
     /// Remove the place if it is present
     Remove(PlaceTag),
@@ -244,6 +598,27 @@ pub struct Place {
     pub coordinates: Coordinates,
 }
 
+#[derive(Args, Clone, Debug)]
+pub struct SetPlace {
+    /// Tag or name of the place, defaults to the name of the locality
+    #[arg(short, long)]
+    pub tag: Option<String>,
+
+    /// Geodetic latitude of the location.
+    /// Latitude must be between -90 and 90 degrees including
+    #[arg(long = "lat", requires = "lon", required_unless_present = "query")]
+    pub lat: Option<f32>,
+
+    /// Geodetic longitude of the location.
+    /// Longitude must be between -180 and 180 degrees including
+    #[arg(long = "lon", requires = "lat", required_unless_present = "query")]
+    pub lon: Option<f32>,
+
+    /// Name of the place to look up instead of coordinates, e.g. "Lviv, UA"
+    #[arg(short, long, conflicts_with_all = ["lat", "lon"])]
+    pub query: Option<String>,
+}
+
 impl fmt::Display for Place {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Place: {}", self.tag.tag)?;
@@ -307,29 +682,282 @@ impl Coordinates {
     }
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct GeoLocation {
+    pub name: String,
+    pub region: Option<String>,
+    pub country: String,
+    pub coordinates: Coordinates,
+}
+
+impl GeoLocation {
+    /// Human readable locality, e.g. "Lviv, Lviv Oblast, UA".
+    pub fn label(&self) -> String {
+        [Some(&self.name), self.region.as_ref(), Some(&self.country)]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for GeoLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (lat: {}, lon: {})",
+            self.label(),
+            self.coordinates.lat,
+            self.coordinates.lon
+        )
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct CityQuery {
+    /// Name of the city, optionally followed by a country code, e.g. "Lviv, UA"
+    pub name: String,
+}
+
 #[derive(Subcommand, Clone, Debug)]
 pub enum Location {
     /// Tag of the place saved to frequently used
     Place(PlaceTag),
 
+    /// Name of the city to look up
+    City(CityQuery),
+
     /// Geodetic coordinate
     Coordinates(Coordinates),
 }
 
 #[derive(ValueEnum, Clone, Debug)]
-pub enum ForecastTime {
+pub enum ForecastPeriod {
     Now,
     Hours24,
     Days3,
     Days5,
 }
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(Args, Clone, Debug)]
+pub struct ForecastRange {
+    /// Time range to get the weather forecast for
+    #[arg(value_enum, default_value_t = ForecastPeriod::Now)]
+    pub time: ForecastPeriod,
+
+    /// Hourly forecast for the given number of hours instead of the time range
+    #[arg(long, conflicts_with_all = ["time", "days"], value_parser = value_parser!(u16).range(1..))]
+    pub hours: Option<u16>,
+
+    /// Daily forecast for the given number of days instead of the time range
+    #[arg(long, conflicts_with = "time", value_parser = value_parser!(u16).range(1..))]
+    pub days: Option<u16>,
+}
+
+impl ForecastRange {
+    pub fn forecast_time(&self) -> ForecastTime {
+        match (self.hours, self.days, &self.time) {
+            (Some(hours), _, _) => ForecastTime::Hours(hours as usize),
+            (_, Some(days), _) => ForecastTime::Days(days as usize),
+            (_, _, ForecastPeriod::Now) => ForecastTime::Now,
+            (_, _, ForecastPeriod::Hours24) => ForecastTime::Hours(24),
+            (_, _, ForecastPeriod::Days3) => ForecastTime::Days(3),
+            (_, _, ForecastPeriod::Days5) => ForecastTime::Days(5),
+        }
+    }
+}
+
+/// Period of the forecast, validated against the provider [`Horizon`].
+#[derive(Clone, Debug)]
+pub enum ForecastTime {
+    Now,
+    Hours(usize),
+    Days(usize),
+}
+
+impl fmt::Display for ForecastTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Now => write!(f, "now"),
+            Self::Hours(n) => write!(f, "hours{}", n),
+            Self::Days(n) => write!(f, "days{}", n),
+        }
+    }
+}
+
+/// Furthest forecast a provider is able to deliver.
+#[derive(Clone, Copy, Debug)]
+pub struct Horizon {
+    pub hours: usize,
+    pub days: usize,
+}
+
+/// Fields of the current weather that providers may leave out.
+pub const OPTIONAL_CURRENT_FIELDS: &[&str] = &["sunrise", "sunset", "precip"];
+
+/// Fields of the hourly forecast that providers may leave out.
+pub const OPTIONAL_HOURLY_FIELDS: &[&str] = &["precip"];
+
+/// Fields of the daily forecast that providers may leave out.
+pub const OPTIONAL_DAILY_FIELDS: &[&str] = &[
+    "avg_temp",
+    "visibility",
+    "pressure",
+    "clouds",
+    "precip",
+    "sunrise",
+    "sunset",
+    "moonrise",
+    "moonset",
+    "moon_phase",
+];
+
+/// What a provider is able to deliver, so requests it can't answer are rejected
+/// before they are made.
+#[derive(Debug)]
+pub struct Capabilities {
+    pub horizon: Horizon,
+    /// Optional fields of the current weather the provider fills in
+    pub current_fields: &'static [&'static str],
+    /// Optional fields of the hourly forecast the provider fills in
+    pub hourly_fields: &'static [&'static str],
+    /// Optional fields of the daily forecast the provider fills in
+    pub daily_fields: &'static [&'static str],
+    pub alerts: bool,
+    /// Scale of the air quality index, if the provider reports it
+    pub air_quality: Option<AqiScale>,
+    /// Minute by minute precipitation for the next hour
+    pub nowcast: bool,
+    /// Past weather of a single date by the hour
+    pub hourly_history: bool,
+    /// Past weather of a date range by the day
+    pub daily_history: bool,
+    /// Languages the condition texts are requested in
+    pub languages: &'static [&'static str],
+    pub units: &'static [UnitType],
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |offered| if offered { "yes" } else { "no" };
+        let fields = |filled: &[&str], optional: &[&str]| {
+            let missing: Vec<&str> = optional
+                .iter()
+                .filter(|field| !filled.contains(field))
+                .copied()
+                .collect();
+            match missing.as_slice() {
+                [] => "every field".to_string(),
+                missing => format!("every field but {}", missing.join(", ")),
+            }
+        };
+
+        writeln!(
+            f,
+            "Forecasts: up to {} hours and {} days",
+            self.horizon.hours, self.horizon.days
+        )?;
+        writeln!(
+            f,
+            "Current weather: {}",
+            fields(self.current_fields, OPTIONAL_CURRENT_FIELDS)
+        )?;
+        writeln!(
+            f,
+            "Hourly forecasts: {}",
+            fields(self.hourly_fields, OPTIONAL_HOURLY_FIELDS)
+        )?;
+        writeln!(
+            f,
+            "Daily forecasts: {}",
+            fields(self.daily_fields, OPTIONAL_DAILY_FIELDS)
+        )?;
+        writeln!(f, "Alerts: {}", yes_no(self.alerts))?;
+        match self.air_quality {
+            Some(scale) => writeln!(f, "Air quality: yes, {} scale", scale)?,
+            None => writeln!(f, "Air quality: no")?,
+        }
+        writeln!(f, "Nowcast: {}", yes_no(self.nowcast))?;
+        writeln!(
+            f,
+            "History: {}",
+            match (self.hourly_history, self.daily_history) {
+                (true, true) => "by the hour for a date, by the day for a range",
+                (true, false) => "by the hour for a date",
+                (false, true) => "by the day",
+                (false, false) => "no",
+            }
+        )?;
+        writeln!(f, "Languages: {}", self.languages.join(", "))?;
+        writeln!(
+            f,
+            "Units: {}",
+            self.units
+                .iter()
+                .map(|u| u.to_string().to_lowercase())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Past dates to look the weather up for, both ends included.
+///
+/// A single date is reported by the hour, a longer range by the day.
+#[derive(Clone, Debug)]
+pub struct HistoryRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl HistoryRange {
+    /// Checks that the range is in order and doesn't go past `today`.
+    pub fn new(from: NaiveDate, to: Option<NaiveDate>, today: NaiveDate) -> Result<Self> {
+        let to = to.unwrap_or(from);
+        if to < from {
+            return Err(crate::errors::AppError::DateRange(format!(
+                "{} is before {}, the range must start with the earlier date",
+                to, from
+            )));
+        }
+        if to > today {
+            return Err(crate::errors::AppError::DateRange(format!(
+                "{} hasn't happened yet, use `forecast` for the coming days",
+                to
+            )));
+        }
+
+        Ok(HistoryRange { from, to })
+    }
+
+    pub fn is_single_day(&self) -> bool {
+        self.from == self.to
+    }
+
+    pub fn days(&self) -> usize {
+        (self.to - self.from).num_days() as usize + 1
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum UnitType {
     Metric,
     Imperial,
 }
 
+impl UnitType {
+    /// Symbols of the temperature, speed, visibility and precipitation units.
+    pub fn symbols(&self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            UnitType::Metric => ("°C", "m/s", "km", "mm"),
+            UnitType::Imperial => ("°F", "mph", "mi", "inches"),
+        }
+    }
+}
+
 impl fmt::Display for UnitType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -339,7 +967,7 @@ impl fmt::Display for UnitType {
     }
 }
 
-fn cardinal_dir_from(degree: f32) -> String {
+pub fn cardinal_dir_from(degree: f32) -> String {
     match degree as u32 % 360 {
         0..=22 => "North",
         23..=67 => "Northeast",
@@ -0,0 +1,50 @@
+mod common;
+
+use std::{env, time::Duration};
+
+use common::StandIn;
+use weather::{
+    cache::CachedProvider,
+    providers::{open_weather::OpenWeather, Provider},
+    weather::Coordinates,
+};
+
+const REVERSE: &str =
+    r#"[{"name": "Lviv", "lat": 49.84, "lon": 24.03, "country": "UA", "state": "Lviv Oblast"}]"#;
+
+#[tokio::test]
+async fn locality_is_looked_up_once_per_place() {
+    // The only test of this binary, so the cache directory can be moved aside.
+    let home = env::temp_dir().join(format!("weather-cache-test-{}", std::process::id()));
+    env::set_var("HOME", &home);
+    env::set_var("XDG_CACHE_HOME", home.join(".cache"));
+
+    let server = StandIn::serve(200, REVERSE).await;
+    let provider = |ttl, offline| {
+        let inner = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+        CachedProvider::new(Box::new(inner), "Open Weather", ttl, offline).unwrap()
+    };
+    // Forecasts are never served from the cache with a TTL of zero, localities still are.
+    let cached = |offline| provider(Duration::ZERO, offline);
+    let coord = Coordinates {
+        lat: 49.841,
+        lon: 24.029,
+    };
+
+    for offline in [false, false, true] {
+        let locality = cached(offline)
+            .reverse_geocode(coord.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(locality.name, "Lviv");
+    }
+    assert_eq!(server.requests().len(), 1);
+
+    // Recording asks the provider every time.
+    let recording = provider(Duration::from_secs(600), false).passthrough();
+    recording.reverse_geocode(coord.clone()).await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+
+    std::fs::remove_dir_all(home).unwrap();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::time::Duration;
+
+use common::StandIn;
+use weather::{
+    cache::CachedProvider,
+    errors::AppError,
+    providers::{self, open_meteo::OpenMeteo, Provider},
+    weather::{Coordinates, ForecastTime},
+};
+
+const NOT_FOUND: &str = r#"{"error": true, "reason": "Not Found"}"#;
+
+#[tokio::test]
+async fn unsupported_requests_are_rejected_without_a_request() {
+    let server = StandIn::serve(404, NOT_FOUND).await;
+    let inner = OpenMeteo::with_base_urls(server.url.clone(), server.url.clone()).unwrap();
+    let provider =
+        CachedProvider::new(Box::new(inner), "Open Meteo", Duration::ZERO, false).unwrap();
+    let coord = Coordinates {
+        lat: 49.84,
+        lon: 24.03,
+    };
+
+    let alerts = provider.get_alerts(coord.clone()).await.unwrap_err();
+    let air = provider
+        .get_air_quality(coord.clone(), ForecastTime::Now)
+        .await
+        .unwrap_err();
+    let nowcast = provider.get_nowcast(coord).await.unwrap_err();
+
+    for error in [alerts, air, nowcast] {
+        assert!(matches!(error, AppError::Unsupported(_)), "{:?}", error);
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn info_lists_the_missing_fields() {
+    let (_, name, capabilities) = providers::AVAILABLE
+        .iter()
+        .find(|(id, ..)| *id == "weather-api")
+        .unwrap();
+    let info = capabilities.to_string();
+
+    assert_eq!(*name, "Weather API");
+    assert!(
+        info.contains("Forecasts: up to 312 hours and 14 days\n"),
+        "{}",
+        info
+    );
+    assert!(
+        info.contains("Daily forecasts: every field but avg_temp, visibility, pressure, clouds\n"),
+        "{}",
+        info
+    );
+    assert!(
+        info.contains("Air quality: yes, US EPA scale\n"),
+        "{}",
+        info
+    );
+    assert!(info.contains("Nowcast: no\n"), "{}", info);
+}
@@ -0,0 +1,56 @@
+use serde_json::{json, Value};
+use weather::weather::{DailyWeather, HourWeather};
+
+/// Hour with everyday values, `fields` overrides them, e.g. `json!({ "temp": 7.5 })`.
+pub fn hour(time: &str, fields: Value) -> HourWeather {
+    let defaults = json!({
+        "time": time,
+        "temp": 8.4,
+        "feels_like": 6.1,
+        "visibility": 10.0,
+        "clouds": 40.0,
+        "humidity": 70.0,
+        "pressure": 1012.0,
+        "wind_speed": 3.0,
+        "wind_deg": 200.0,
+        "uvi": 0.0,
+        "condition": "light rain",
+        "precip": null,
+        "unit": "metric",
+    });
+
+    serde_json::from_value(merge(defaults, fields)).unwrap()
+}
+
+/// Day with everyday values, `fields` overrides them like for [`hour`].
+pub fn day(date: &str, fields: Value) -> DailyWeather {
+    let defaults = json!({
+        "date": date,
+        "min_temp": 3.1,
+        "max_temp": 9.8,
+        "avg_temp": 6.5,
+        "visibility": null,
+        "humidity": 70.0,
+        "pressure": null,
+        "wind_speed": 3.0,
+        "uvi": 1.0,
+        "clouds": null,
+        "condition": "overcast clouds",
+        "precip": null,
+        "sunrise": null,
+        "sunset": null,
+        "moonrise": null,
+        "moonset": null,
+        "moon_phase": null,
+        "unit": "metric",
+    });
+
+    serde_json::from_value(merge(defaults, fields)).unwrap()
+}
+
+fn merge(mut defaults: Value, fields: Value) -> Value {
+    if let (Value::Object(defaults), Value::Object(fields)) = (&mut defaults, fields) {
+        defaults.extend(fields);
+    }
+    defaults
+}
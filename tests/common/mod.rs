@@ -0,0 +1,73 @@
+// Every test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+pub mod entries;
+
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use url::Url;
+
+/// Local HTTP server answering requests with recorded replies.
+pub struct StandIn {
+    pub url: Url,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StandIn {
+    /// Answers every request with the same reply.
+    pub async fn serve(status: u16, body: &'static str) -> StandIn {
+        StandIn::serve_in_turn(vec![(status, body)]).await
+    }
+
+    /// Answers the requests with the replies in order, the last one repeated.
+    pub async fn serve_in_turn(replies: Vec<(u16, &'static str)>) -> StandIn {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                // Requests are bodiless GETs, the head is all there is to read.
+                let mut head = Vec::new();
+                let mut buf = [0; 1024];
+                while !head.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf).await.unwrap() {
+                        0 => break,
+                        n => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let head = String::from_utf8_lossy(&head);
+                let target = head.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = {
+                    let mut received = received.lock().unwrap();
+                    received.push(target.to_string());
+                    replies[(received.len() - 1).min(replies.len() - 1)]
+                };
+
+                let reply = format!(
+                    "HTTP/1.1 {} Stand-In\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        StandIn { url, requests }
+    }
+
+    /// Paths with queries of the requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
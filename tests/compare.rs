@@ -0,0 +1,87 @@
+mod common;
+
+use chrono::Utc;
+use common::entries::hour;
+use serde_json::json;
+use weather::{
+    cache::CachedForecast,
+    compare,
+    weather::{HourWeather, TimeFormat, UnitType, Weather},
+};
+
+fn hourly(hours: Vec<HourWeather>) -> CachedForecast {
+    CachedForecast {
+        weather: Weather::Today(hours),
+        fetched_at: Utc::now(),
+        from_cache: false,
+    }
+}
+
+/// Values of the temperature row, collapsed to single spaces.
+fn temperatures(section: &str) -> String {
+    let row = section
+        .lines()
+        .find(|l| l.starts_with("Temperature"))
+        .unwrap();
+    row.split_whitespace()
+        .skip(1)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[test]
+fn hours_are_lined_up_by_time() {
+    let results = [
+        (
+            "first".to_string(),
+            Ok(hourly(vec![
+                hour("2023-11-01T18:00:00+02:00", json!({ "temp": 8.0 })),
+                hour("2023-11-01T19:00:00+02:00", json!({ "temp": 7.0 })),
+            ])),
+        ),
+        (
+            "second".to_string(),
+            // Starts an hour later.
+            Ok(hourly(vec![
+                hour("2023-11-01T19:00:00+02:00", json!({ "temp": 6.0 })),
+                hour("2023-11-01T20:00:00+02:00", json!({ "temp": 5.0 })),
+            ])),
+        ),
+    ];
+
+    let out = compare::render(&UnitType::Metric, &TimeFormat::H24, &results);
+    let sections = out.split("\n\n").collect::<Vec<&str>>();
+
+    assert_eq!(sections[0].lines().next(), Some("Time: 2023-11-01 18:00"));
+    assert_eq!(temperatures(sections[0]), "8.00 °C -");
+    assert_eq!(sections[1].lines().next(), Some("Time: 2023-11-01 19:00"));
+    assert_eq!(temperatures(sections[1]), "7.00 °C 6.00 °C");
+    assert_eq!(sections[2].lines().next(), Some("Time: 2023-11-01 20:00"));
+    // The first provider has no forecast for the last hour.
+    assert_eq!(temperatures(sections[2]), "- 5.00 °C");
+}
+
+#[test]
+fn imperial_precipitation_is_compared_in_inches() {
+    let results = [
+        (
+            "first".to_string(),
+            Ok(hourly(vec![hour(
+                "2023-11-01T18:00:00+02:00",
+                json!({ "temp": 46.0, "precip": 0.05, "unit": "imperial" }),
+            )])),
+        ),
+        (
+            "second".to_string(),
+            Ok(hourly(vec![hour(
+                "2023-11-01T18:00:00+02:00",
+                json!({ "temp": 46.0, "precip": 0.0, "unit": "imperial" }),
+            )])),
+        ),
+    ];
+
+    let out = compare::render(&UnitType::Imperial, &TimeFormat::H24, &results);
+
+    // About a millimetre against none at all.
+    assert!(out.contains("! precipitation disagreement"), "{}", out);
+}
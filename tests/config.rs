@@ -0,0 +1,59 @@
+use weather::{
+    config::WeatherConfig,
+    weather::{Provider, ProviderCredentials},
+};
+
+fn open_weather(env: &str) -> Provider {
+    Provider::OpenWeather(ProviderCredentials {
+        key: None,
+        key_env: Some(env.to_string()),
+        key_command: None,
+        key_file: None,
+    })
+}
+
+#[test]
+fn providers_of_the_same_kind_can_share_the_chain() {
+    let mut config = WeatherConfig::default();
+    config.set_primary(open_weather("WORK_KEY"));
+    config.add_fallback(open_weather("HOME_KEY"));
+    config.add_fallback(Provider::OpenMeteo);
+
+    assert_eq!(
+        config.providers,
+        [
+            open_weather("WORK_KEY"),
+            open_weather("HOME_KEY"),
+            Provider::OpenMeteo
+        ]
+    );
+}
+
+#[test]
+fn configuring_a_provider_again_moves_it() {
+    let mut config = WeatherConfig::default();
+    config.set_primary(Provider::OpenMeteo);
+    config.add_fallback(open_weather("WORK_KEY"));
+    config.set_primary(open_weather("WORK_KEY"));
+
+    assert_eq!(
+        config.providers,
+        [open_weather("WORK_KEY"), Provider::OpenMeteo]
+    );
+}
+
+#[test]
+fn providers_with_other_credentials_have_their_own_cache() {
+    assert_ne!(
+        open_weather("WORK_KEY").cache_id(),
+        open_weather("HOME_KEY").cache_id()
+    );
+    assert_eq!(
+        open_weather("WORK_KEY").cache_id(),
+        open_weather("WORK_KEY").cache_id()
+    );
+    assert!(open_weather("WORK_KEY")
+        .cache_id()
+        .starts_with("open-weather-"));
+    assert_eq!(Provider::OpenMeteo.cache_id(), "open-meteo");
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Duration};
+use weather::{nowcast, weather::MinutePrecip};
+
+fn minutes(precip: &[f32]) -> Vec<MinutePrecip> {
+    let start = DateTime::parse_from_rfc3339("2023-11-01T18:30:00+02:00").unwrap();
+
+    precip
+        .iter()
+        .enumerate()
+        .map(|(i, &precip)| MinutePrecip {
+            time: start + Duration::minutes(i as i64),
+            precip,
+        })
+        .collect()
+}
+
+/// An hour with the given intensity during `wet` and dry otherwise.
+fn hour(wet: std::ops::Range<usize>, precip: f32) -> Vec<MinutePrecip> {
+    let precip: Vec<f32> = (0..60)
+        .map(|i| if wet.contains(&i) { precip } else { 0.0 })
+        .collect();
+    minutes(&precip)
+}
+
+#[test]
+fn summary_tells_when_the_rain_starts_and_stops() {
+    for (minutes, summary) in [
+        (
+            hour(12..35, 1.0),
+            "Rain starting in 12 min, stopping in 35 min",
+        ),
+        (hour(0..20, 1.0), "Rain stopping in 20 min"),
+        (
+            hour(40..60, 1.0),
+            "Rain starting in 40 min and lasting for at least 20 min",
+        ),
+        (hour(0..60, 1.0), "Rain for at least the next 60 min"),
+        (hour(0..0, 0.0), "No rain within the next 60 min"),
+        // Traces below the threshold don't count as rain.
+        (hour(10..20, 0.05), "No rain within the next 60 min"),
+    ] {
+        assert_eq!(nowcast::summary(&minutes), summary);
+    }
+
+    let mut showers = hour(0..10, 1.0);
+    showers[30].precip = 2.0;
+    assert_eq!(
+        nowcast::summary(&showers),
+        "Rain stopping in 10 min, starting again in 30 min"
+    );
+}
+
+#[test]
+fn sparkline_has_a_bar_per_minute() {
+    assert_eq!(
+        nowcast::sparkline(&minutes(&[0.0, 0.2, 1.0, 2.0, 4.0, 0.0])),
+        " ▁▃▅█ "
+    );
+    // Heavier rain than the full bar is scaled to its peak.
+    assert_eq!(nowcast::sparkline(&minutes(&[8.0, 4.0, 0.0])), "█▅ ");
+}
@@ -0,0 +1,337 @@
+mod common;
+
+use chrono::{DateTime, NaiveDate};
+use common::StandIn;
+use weather::{
+    errors::AppError,
+    providers::{open_weather::OpenWeather, Provider},
+    weather::{AirForecast, AqiScale, Coordinates, ForecastTime, HistoryRange, UnitType, Weather},
+};
+
+const ONECALL: &str = include_str!("fixtures/onecall.json");
+const ONECALL_POLAR: &str = include_str!("fixtures/onecall_polar.json");
+const AIR_POLLUTION: &str = include_str!("fixtures/air_pollution.json");
+const TIMEMACHINE: &str = include_str!("fixtures/timemachine.json");
+const ONECALL_MINUTELY: &str = include_str!("fixtures/onecall_minutely.json");
+
+fn lviv() -> Coordinates {
+    Coordinates {
+        lat: 49.84,
+        lon: 24.03,
+    }
+}
+
+fn history(from: (i32, u32, u32), to: Option<(i32, u32, u32)>) -> HistoryRange {
+    let date = |(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    HistoryRange::new(date(from), to.map(date), date((2023, 11, 30))).unwrap()
+}
+
+async fn forecast(body: &'static str, time: ForecastTime) -> Weather {
+    let server = StandIn::serve(200, body).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    provider
+        .get_forecast(lviv(), time, UnitType::Metric)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn current_weather_is_normalized() {
+    let Weather::Current(current) = forecast(ONECALL, ForecastTime::Now).await else {
+        panic!("expected the current weather");
+    };
+
+    assert_eq!(current.temp, 8.4);
+    assert_eq!(current.feels_like, 6.1);
+    assert_eq!(current.visibility, 9.0);
+    assert_eq!(current.wind_deg, 210.0);
+    assert_eq!(current.precip, Some(0.42));
+    assert_eq!(current.condition, "light rain");
+    assert_eq!(
+        current.sunrise,
+        Some(DateTime::parse_from_rfc3339("2023-11-01T07:02:00+02:00").unwrap())
+    );
+    assert_eq!(
+        current.sunset,
+        Some(DateTime::parse_from_rfc3339("2023-11-01T16:47:00+02:00").unwrap())
+    );
+}
+
+#[tokio::test]
+async fn hours_are_in_local_time_and_rain_is_optional() {
+    let Weather::Today(hours) = forecast(ONECALL, ForecastTime::Hours(2)).await else {
+        panic!("expected an hourly forecast");
+    };
+
+    assert_eq!(hours.len(), 2);
+    assert_eq!(hours[0].time.to_rfc3339(), "2023-11-01T18:00:00+02:00");
+    assert_eq!(hours[0].precip, Some(0.42));
+    assert_eq!(hours[1].time.to_rfc3339(), "2023-11-01T19:00:00+02:00");
+    assert_eq!(hours[1].precip, None);
+    assert_eq!(hours[1].condition, "overcast clouds");
+    assert_eq!(hours[1].visibility, 10.0);
+}
+
+#[tokio::test]
+async fn days_are_normalized() {
+    let Weather::Daily(days) = forecast(ONECALL, ForecastTime::Days(2)).await else {
+        panic!("expected a daily forecast");
+    };
+
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2023, 11, 1).unwrap());
+    assert_eq!(days[0].min_temp, 6.8);
+    assert_eq!(days[0].max_temp, 11.3);
+    assert_eq!(days[0].precip, Some(4.35));
+    assert_eq!(days[0].moon_phase.as_deref(), Some("0.60"));
+    assert_eq!(
+        days[0].moonrise.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-11-01T19:12:00+02:00")
+    );
+    assert_eq!(days[1].date, NaiveDate::from_ymd_opt(2023, 11, 2).unwrap());
+    assert_eq!(days[1].precip, None);
+}
+
+#[tokio::test]
+async fn polar_night_has_no_sunrise() {
+    let Weather::Current(current) = forecast(ONECALL_POLAR, ForecastTime::Now).await else {
+        panic!("expected the current weather");
+    };
+    assert_eq!(current.sunrise, None);
+    assert_eq!(current.sunset, None);
+    assert_eq!(current.precip, None);
+
+    let Weather::Daily(days) = forecast(ONECALL_POLAR, ForecastTime::Days(1)).await else {
+        panic!("expected a daily forecast");
+    };
+    assert_eq!(days[0].sunrise, None);
+    assert_eq!(days[0].sunset, None);
+    assert_eq!(days[0].moonset, None);
+    assert_eq!(
+        days[0].moonrise.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-12-15T10:58:00+01:00")
+    );
+}
+
+#[tokio::test]
+async fn alerts_are_normalized() {
+    let server = StandIn::serve(200, ONECALL).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let alerts = provider.get_alerts(lviv()).await.unwrap();
+
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].event, "Strong wind");
+    assert_eq!(
+        alerts[0].sender.as_deref(),
+        Some("Ukrainian Hydrometeorological Center")
+    );
+    assert_eq!(
+        alerts[0].start.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-11-01T20:00:00+02:00")
+    );
+}
+
+#[tokio::test]
+async fn request_carries_coordinates_units_and_key() {
+    let server = StandIn::serve(200, ONECALL).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    provider
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Imperial)
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("/data/3.0/onecall?"));
+    assert!(requests[0].contains("lat=49.84&lon=24.03"));
+    assert!(requests[0].contains("appid=secret"));
+    assert!(requests[0].contains("units=imperial"));
+}
+
+#[tokio::test]
+async fn rejected_key_is_reported() {
+    let server = StandIn::serve(
+        401,
+        r#"{"cod":401, "message": "Invalid API key. Please see https://openweathermap.org/faq#error401 for more info."}"#,
+    )
+    .await;
+    let provider = OpenWeather::with_base_url("wrong".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::InvalidKey(ref m) if m.starts_with("Invalid API key")));
+}
+
+#[tokio::test]
+async fn unexpected_payload_is_a_schema_change() {
+    let server = StandIn::serve(200, r#"{"timezone_offset": 7200}"#).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::ProviderSchemaChanged(_)));
+}
+
+#[tokio::test]
+async fn air_quality_is_on_the_open_weather_scale() {
+    let server = StandIn::serve(200, AIR_POLLUTION).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let AirForecast::Current(air) = provider
+        .get_air_quality(lviv(), ForecastTime::Now)
+        .await
+        .unwrap()
+    else {
+        panic!("expected the current air quality");
+    };
+
+    assert_eq!(air.aqi, 2);
+    assert_eq!(air.scale, AqiScale::OpenWeather);
+    assert_eq!(air.scale.band(air.aqi), "Fair");
+    assert_eq!(air.pm2_5, 7.12);
+    assert_eq!(air.no2, 9.6);
+    // The air pollution API doesn't report the time zone.
+    assert_eq!(air.time.to_rfc3339(), "2023-11-01T16:00:00+00:00");
+
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/data/2.5/air_pollution?"));
+}
+
+#[tokio::test]
+async fn air_quality_forecast_is_hourly() {
+    let server = StandIn::serve(200, AIR_POLLUTION).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let AirForecast::Hourly(hours) = provider
+        .get_air_quality(lviv(), ForecastTime::Hours(2))
+        .await
+        .unwrap()
+    else {
+        panic!("expected an hourly air quality forecast");
+    };
+
+    assert_eq!(hours.iter().map(|h| h.aqi).collect::<Vec<_>>(), [2, 1]);
+    assert!(server.requests()[0].starts_with("/data/2.5/air_pollution/forecast?"));
+
+    let error = provider
+        .get_air_quality(lviv(), ForecastTime::Days(2))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AppError::Unsupported(_)));
+}
+
+#[tokio::test]
+async fn history_day_asks_for_every_local_hour() {
+    let server = StandIn::serve(200, TIMEMACHINE).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let Weather::Today(hours) = provider
+        .get_history(lviv(), history((2023, 11, 1), None), UnitType::Metric)
+        .await
+        .unwrap()
+    else {
+        panic!("expected the hours of the day");
+    };
+
+    // The stand-in answers every hour with the same one.
+    assert_eq!(hours.len(), 1);
+    assert_eq!(hours[0].time.to_rfc3339(), "2023-11-01T14:00:00+02:00");
+    assert_eq!(hours[0].condition, "broken clouds");
+    assert_eq!(hours[0].visibility, 10.0);
+
+    let requests = server.requests();
+    assert!(requests
+        .iter()
+        .all(|r| r.starts_with("/data/3.0/onecall/timemachine?")));
+    // Noon UTC first, then from the local midnight to 23:00.
+    assert!(requests[0].contains("dt=1698840000"));
+    assert!(requests.iter().any(|r| r.contains("dt=1698789600")));
+    assert!(requests.iter().any(|r| r.contains("dt=1698872400")));
+}
+
+#[tokio::test]
+async fn history_day_reports_the_hours_that_failed() {
+    let server = StandIn::serve_in_turn(vec![(200, TIMEMACHINE), (503, "{}")]).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_history(lviv(), history((2023, 11, 1), None), UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    let AppError::IncompleteHistory(message) = error else {
+        panic!("expected an incomplete history, got {:?}", error);
+    };
+    assert!(
+        message.starts_with("no reply for 00:00, 01:00,"),
+        "{}",
+        message
+    );
+    assert!(!message.contains("14:00"), "{}", message);
+}
+
+#[tokio::test]
+async fn history_day_stops_at_a_rejected_key() {
+    let server = StandIn::serve_in_turn(vec![(200, TIMEMACHINE), (401, "{}")]).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_history(lviv(), history((2023, 11, 1), None), UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::InvalidKey(_)), "{:?}", error);
+}
+
+#[tokio::test]
+async fn history_ranges_are_unsupported() {
+    let server = StandIn::serve(200, TIMEMACHINE).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_history(
+            lviv(),
+            history((2023, 11, 1), Some((2023, 11, 3))),
+            UnitType::Metric,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::Unsupported(_)));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn nowcast_is_minute_by_minute() {
+    let server = StandIn::serve(200, ONECALL_MINUTELY).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let minutes = provider.get_nowcast(lviv()).await.unwrap();
+
+    assert_eq!(minutes.len(), 60);
+    assert_eq!(minutes[0].time.to_rfc3339(), "2023-11-01T18:30:00+02:00");
+    assert_eq!(minutes[12].precip, 0.3);
+
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/data/3.0/onecall?"));
+    assert!(!requests[0].contains("minutely"), "{}", requests[0]);
+}
+
+#[tokio::test]
+async fn nowcast_outside_coverage_is_unsupported() {
+    let server = StandIn::serve(200, ONECALL).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider.get_nowcast(lviv()).await.unwrap_err();
+
+    assert!(matches!(error, AppError::Unsupported(_)));
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::StandIn;
+use reqwest::StatusCode;
+use url::Url;
+use weather::{
+    errors::AppError,
+    providers::{open_weather, replay, Fetcher, Provider},
+    weather::{Coordinates, ForecastTime, UnitType, Weather},
+};
+
+const ONECALL: &str = include_str!("fixtures/onecall.json");
+
+fn lviv() -> Coordinates {
+    Coordinates {
+        lat: 49.84,
+        lon: 24.03,
+    }
+}
+
+fn empty_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("weather-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn recorded_reply_is_replayed() {
+    let dir = empty_dir("replay");
+    let server = StandIn::serve(200, ONECALL).await;
+    let recorder =
+        open_weather::OpenWeather::with_base_url("secret".to_string(), server.url.clone())
+            .unwrap()
+            .with_fetcher(Fetcher::new(open_weather::ID).unwrap().recording(&dir));
+
+    let Weather::Today(live) = recorder
+        .get_forecast(lviv(), ForecastTime::Hours(3), UnitType::Metric)
+        .await
+        .unwrap()
+    else {
+        panic!("expected an hourly forecast");
+    };
+
+    let recordings = fs::read_dir(&dir).unwrap().collect::<Vec<_>>();
+    assert_eq!(recordings.len(), 1);
+    let saved = fs::read_to_string(recordings[0].as_ref().unwrap().path()).unwrap();
+    assert!(!saved.contains("secret"));
+    assert!(saved.contains("appid=REDACTED"));
+
+    let Weather::Today(replayed) = replay::from_dir(&dir)
+        .unwrap()
+        .get_forecast(lviv(), ForecastTime::Hours(3), UnitType::Metric)
+        .await
+        .unwrap()
+    else {
+        panic!("expected an hourly forecast");
+    };
+
+    assert_eq!(
+        serde_json::to_string(&replayed).unwrap(),
+        serde_json::to_string(&live).unwrap()
+    );
+    // Replaying makes no requests.
+    assert_eq!(server.requests().len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn missing_reply_is_reported() {
+    let dir = empty_dir("missing");
+    let server = StandIn::serve(200, ONECALL).await;
+    let recorder =
+        open_weather::OpenWeather::with_base_url("secret".to_string(), server.url.clone())
+            .unwrap()
+            .with_fetcher(Fetcher::new(open_weather::ID).unwrap().recording(&dir));
+    recorder
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap();
+
+    // Only the forecast was recorded, there is nothing to geocode with.
+    let error = replay::from_dir(&dir)
+        .unwrap()
+        .geocode("Lviv")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::NoRecording(_)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn reply_for_another_query_is_not_replayed() {
+    let dir = empty_dir("other-query");
+    let server = StandIn::serve(200, ONECALL).await;
+    let recorder =
+        open_weather::OpenWeather::with_base_url("secret".to_string(), server.url.clone())
+            .unwrap()
+            .with_fetcher(Fetcher::new(open_weather::ID).unwrap().recording(&dir));
+    recorder
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap();
+
+    // Same endpoint, but Kyiv was never recorded.
+    let kyiv = Coordinates {
+        lat: 50.45,
+        lon: 30.52,
+    };
+    let error = replay::from_dir(&dir)
+        .unwrap()
+        .get_forecast(kyiv, ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    let AppError::NoRecording(message) = error else {
+        panic!("expected a missing recording");
+    };
+    assert!(message.starts_with("no recording for this request"));
+    assert!(message.contains("lat=50.45"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn simultaneous_recordings_are_all_kept() {
+    let dir = empty_dir("simultaneous");
+    let url = Url::parse("https://api.example.com/data/3.0/onecall/timemachine?dt=1").unwrap();
+
+    for _ in 0..10 {
+        replay::Recording::new(open_weather::ID, &url, StatusCode::OK, "{}")
+            .save(&dir)
+            .unwrap();
+    }
+
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 10);
+    fs::remove_dir_all(&dir).unwrap();
+}
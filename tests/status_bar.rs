@@ -0,0 +1,94 @@
+mod common;
+
+use common::StandIn;
+use serde_json::Value;
+use weather::{
+    output::{self, WaybarStatus},
+    providers::{open_weather::OpenWeather, Provider},
+    weather::{
+        Coordinates, CurrentWeather, ForecastTime, HourWeather, TimeFormat, UnitType, Weather,
+    },
+};
+
+const ONECALL: &str = include_str!("fixtures/onecall.json");
+
+async fn forecast(time: ForecastTime) -> Weather {
+    let server = StandIn::serve(200, ONECALL).await;
+    let provider = OpenWeather::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+    let coord = Coordinates {
+        lat: 49.84,
+        lon: 24.03,
+    };
+
+    let weather = provider
+        .get_forecast(coord, time, UnitType::Metric)
+        .await
+        .unwrap();
+    assert_eq!(server.requests().len(), 1);
+    weather
+}
+
+async fn current() -> CurrentWeather {
+    match forecast(ForecastTime::Now).await {
+        Weather::Current(current) => current,
+        other => panic!("expected the current weather, got {:?}", other),
+    }
+}
+
+async fn hours() -> Vec<HourWeather> {
+    // The fixture has three hours, fewer than the tooltip would take.
+    match forecast(ForecastTime::Hours(3)).await {
+        Weather::Today(hours) => hours,
+        other => panic!("expected an hourly forecast, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn waybar_status_carries_the_next_hours() {
+    let status = WaybarStatus::new(
+        "Open Weather",
+        Some("Lviv, UA"),
+        &current().await,
+        &hours().await,
+        &TimeFormat::H24,
+    );
+    let json: Value = serde_json::from_str(&status.to_json().unwrap()).unwrap();
+
+    assert_eq!(json["text"], "8°C light rain");
+    assert_eq!(json["class"], "rain");
+    assert_eq!(json["alt"], "rain");
+
+    let tooltip = json["tooltip"].as_str().unwrap();
+    assert!(tooltip.starts_with("Lviv, UA, Open Weather\nFeels like 6°C"));
+    assert!(tooltip.contains("\n18:00  8°C  light rain"), "{}", tooltip);
+    assert!(
+        tooltip.contains("\n19:00  8°C  overcast clouds"),
+        "{}",
+        tooltip
+    );
+}
+
+#[tokio::test]
+async fn i3blocks_prints_full_text_short_text_and_color() {
+    assert_eq!(
+        output::i3blocks(&current().await),
+        "8°C light rain\n8°C\n#5fafff\n"
+    );
+}
+
+#[test]
+fn condition_class_covers_provider_wordings() {
+    for (condition, class) in [
+        ("Patchy light snow", "snow"),
+        ("Moderate or heavy rain with thunder", "storm"),
+        ("light rain", "rain"),
+        ("Light drizzle", "rain"),
+        ("Sunny", "clear"),
+        ("clear sky", "clear"),
+        ("overcast clouds", "cloudy"),
+        ("Freezing fog", "fog"),
+        ("Volcanic ash", "unknown"),
+    ] {
+        assert_eq!(output::condition_class(condition), class, "{}", condition);
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::entries::hour;
+use serde_json::json;
+use weather::{
+    table::{self, Column, DEFAULT_COLUMNS},
+    weather::{HourWeather, TimeFormat},
+};
+
+fn evening() -> Vec<HourWeather> {
+    vec![
+        hour(
+            "2023-11-01T18:00:00+02:00",
+            json!({ "temp": 8.4, "feels_like": 6.4, "condition": "scattered clouds" }),
+        ),
+        hour(
+            "2023-11-01T19:00:00+02:00",
+            json!({
+                "temp": 7.9,
+                "feels_like": 5.9,
+                "condition": "light rain and snow showers",
+            }),
+        ),
+    ]
+}
+
+#[test]
+fn every_hour_is_a_row() {
+    let out = table::hours(&evening(), &DEFAULT_COLUMNS, &TimeFormat::H24, usize::MAX);
+
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        [
+            "Time              Temp    Feels like  Wind           Precip  Condition",
+            "2023-11-01 18:00  8.4 °C  6.4 °C      3.0 m/s South  -       scattered clouds",
+            "2023-11-01 19:00  7.9 °C  5.9 °C      3.0 m/s South  -       light rain and snow showers",
+        ]
+    );
+}
+
+#[test]
+fn narrow_terminal_cuts_condition_then_drops_columns() {
+    let columns = [Column::Time, Column::Condition, Column::Temp];
+
+    let cut = table::hours(&evening(), &columns, &TimeFormat::H24, 40);
+    assert!(cut.lines().all(|l| l.chars().count() <= 40));
+    assert!(cut.contains("light rain an…"));
+    assert!(cut.contains("7.9 °C"));
+
+    let dropped = table::hours(&evening(), &columns, &TimeFormat::H24, 28);
+    assert!(dropped.lines().all(|l| l.chars().count() <= 28));
+    assert!(!dropped.contains("°C"));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::entries;
+use serde_json::json;
+use weather::{
+    errors::AppError,
+    template::{EntryKind, Fields, Template},
+    weather::{DailyWeather, HourWeather, TimeFormat},
+};
+
+fn hour() -> HourWeather {
+    entries::hour("2023-11-01T18:00:00+02:00", json!({ "precip": 0.4 }))
+}
+
+fn day() -> DailyWeather {
+    entries::day(
+        "2023-11-01",
+        json!({
+            "sunrise": "2023-11-01T07:04:00+02:00",
+            "sunset": "2023-11-01T16:51:00+02:00",
+        }),
+    )
+}
+
+fn render(template: &str, entry: &impl weather::template::Fields) -> String {
+    Template::parse(template)
+        .unwrap()
+        .render(entry, &TimeFormat::H24)
+        .unwrap()
+}
+
+#[test]
+fn fields_of_every_entry_are_available() {
+    assert_eq!(
+        render(
+            "{time} {temp}°{unit} {condition}, {precip} {precip_unit}",
+            &hour()
+        ),
+        "2023-11-01 18:00 8.4°C light rain, 0.4 mm"
+    );
+    assert_eq!(
+        render(
+            "{date} {condition} ↑{max} ↓{min}, sunset {sunset}, moonset {moonset}",
+            &day()
+        ),
+        "2023-11-01 overcast clouds ↑9.8 ↓3.1, sunset 16:51, moonset -"
+    );
+}
+
+#[test]
+fn precision_and_padding_follow_rust_format() {
+    assert_eq!(
+        render(
+            "[{temp:>6.2}] [{temp:.0}] [{condition:.<12}] [{wind_dir:^7}] {{x}}",
+            &hour()
+        ),
+        "[  8.40] [8] [light rain..] [ South ] {x}"
+    );
+}
+
+#[test]
+fn unknown_field_is_reported_with_the_available_ones() {
+    let template = Template::parse("{temp} {max}").unwrap();
+
+    match template.render(&hour(), &TimeFormat::H24) {
+        Err(AppError::Template(e)) => {
+            assert!(e.starts_with("unknown field `max`"), "{}", e);
+            assert!(e.contains("feels_like"), "{}", e);
+        }
+        other => panic!("expected a template error, got {:?}", other),
+    }
+}
+
+#[test]
+fn misspelled_field_is_rejected_when_parsing() {
+    match Template::parse("{temp} {feelslike}") {
+        Err(AppError::Template(e)) => {
+            assert!(e.starts_with("unknown field `feelslike`"), "{}", e);
+            assert!(e.contains("feels_like"), "{}", e);
+        }
+        other => panic!("expected a template error, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_kind_lists_the_fields_it_renders() {
+    let hour = hour().fields(&TimeFormat::H24).unwrap();
+    let day = day().fields(&TimeFormat::H24).unwrap();
+
+    assert!(hour.keys().eq(EntryKind::Hour.fields()));
+    assert!(day.keys().eq(EntryKind::Day.fields()));
+}
+
+#[test]
+fn fields_of_another_kind_are_rejected_before_fetching() {
+    for (template, kind) in [("{max}", EntryKind::Current), ("{time}", EntryKind::Day)] {
+        match Template::parse(template).unwrap().check(kind) {
+            Err(AppError::Template(e)) => assert!(e.starts_with("unknown field"), "{}", e),
+            other => panic!("{} was accepted for {:?}: {:?}", template, kind, other),
+        }
+    }
+    assert!(Template::parse("{max} {date}")
+        .unwrap()
+        .check(EntryKind::Day)
+        .is_ok());
+}
+
+#[test]
+fn malformed_templates_are_rejected() {
+    for template in ["{temp", "temp}", "{temp:x}", "{:.1}", "{temp:.}"] {
+        assert!(
+            matches!(Template::parse(template), Err(AppError::Template(_))),
+            "{} was accepted",
+            template
+        );
+    }
+}
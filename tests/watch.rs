@@ -0,0 +1,73 @@
+mod common;
+
+use std::time::Duration;
+
+use common::entries;
+use serde_json::json;
+use weather::{
+    watch,
+    weather::{HourWeather, TimeFormat, Weather},
+};
+
+fn hour(time: &str, temp: f32, precip: Option<f32>) -> HourWeather {
+    entries::hour(time, json!({ "temp": temp, "precip": precip }))
+}
+
+#[test]
+fn hours_are_matched_by_time() {
+    let prev = Weather::Today(vec![
+        hour("2023-11-01T18:00:00+02:00", 8.0, None),
+        hour("2023-11-01T19:00:00+02:00", 7.5, Some(0.0)),
+    ]);
+    // The window moved by an hour, only the overlapping one is compared.
+    let next = Weather::Today(vec![
+        hour("2023-11-01T19:00:00+02:00", 6.0, Some(1.2)),
+        hour("2023-11-01T20:00:00+02:00", 5.0, Some(2.0)),
+    ]);
+
+    assert_eq!(
+        watch::changes(&prev, &next, &TimeFormat::H24),
+        [
+            "2023-11-01 19:00: temperature -1.5 °C, now 6.0 °C",
+            "2023-11-01 19:00: precipitation started, 1.2 mm",
+        ]
+    );
+}
+
+#[test]
+fn unchanged_forecast_has_no_changes() {
+    let prev = Weather::Today(vec![hour("2023-11-01T18:00:00+02:00", 8.0, Some(0.05))]);
+    let next = Weather::Today(vec![hour("2023-11-01T18:00:00+02:00", 8.05, Some(0.0))]);
+
+    assert!(watch::changes(&prev, &next, &TimeFormat::H24).is_empty());
+}
+
+#[test]
+fn imperial_precipitation_is_measured_in_inches() {
+    let imperial = |precip| {
+        entries::hour(
+            "2023-11-01T18:00:00+02:00",
+            json!({ "temp": 46.0, "precip": precip, "unit": "imperial" }),
+        )
+    };
+    let prev = Weather::Today(vec![imperial(0.0)]);
+    // About a millimetre, light rain.
+    let next = Weather::Today(vec![imperial(0.05)]);
+
+    assert_eq!(
+        watch::changes(&prev, &next, &TimeFormat::H24),
+        ["2023-11-01 18:00: precipitation started, 0.1 inches"]
+    );
+}
+
+#[test]
+fn failures_back_off_up_to_the_limit() {
+    let interval = Duration::from_secs(60);
+
+    assert_eq!(watch::next_delay(interval, 0), interval);
+    assert_eq!(watch::next_delay(interval, 3), Duration::from_secs(480));
+    assert_eq!(
+        watch::next_delay(interval, 40),
+        Duration::from_secs(30 * 60)
+    );
+}
@@ -0,0 +1,358 @@
+mod common;
+
+use chrono::{DateTime, NaiveDate};
+use common::StandIn;
+use weather::{
+    errors::AppError,
+    providers::{weather_api::WeatherApi, Provider},
+    secret::ApiKey,
+    weather::{
+        AirForecast, AqiScale, Coordinates, ForecastTime, HistoryRange, ProviderCredentials,
+        UnitType, Weather,
+    },
+};
+
+const FORECAST: &str = include_str!("fixtures/forecast.json");
+const FORECAST_POLAR: &str = include_str!("fixtures/forecast_polar.json");
+const FORECAST_AIR: &str = include_str!("fixtures/forecast_air.json");
+const HISTORY: &str = include_str!("fixtures/history.json");
+
+fn lviv() -> Coordinates {
+    Coordinates {
+        lat: 49.84,
+        lon: 24.03,
+    }
+}
+
+fn history(from: (i32, u32, u32), to: Option<(i32, u32, u32)>) -> HistoryRange {
+    let date = |(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    HistoryRange::new(date(from), to.map(date), date((2023, 11, 30))).unwrap()
+}
+
+async fn forecast(body: &'static str, time: ForecastTime, unit: UnitType) -> Weather {
+    let server = StandIn::serve(200, body).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    provider.get_forecast(lviv(), time, unit).await.unwrap()
+}
+
+#[tokio::test]
+async fn current_weather_is_normalized() {
+    let Weather::Current(current) = forecast(FORECAST, ForecastTime::Now, UnitType::Metric).await
+    else {
+        panic!("expected the current weather");
+    };
+
+    assert_eq!(current.temp, 8.0);
+    assert_eq!(current.feels_like, 5.6);
+    // Reported in km/h, normalized to m/s.
+    assert!((current.wind_speed - 15.1 / 3.6).abs() < 1e-4);
+    assert_eq!(current.precip, Some(0.3));
+    assert_eq!(current.condition, "Light rain");
+    assert_eq!(
+        current.sunrise,
+        Some(DateTime::parse_from_rfc3339("2023-11-01T07:02:00+02:00").unwrap())
+    );
+    assert_eq!(
+        current.sunset,
+        Some(DateTime::parse_from_rfc3339("2023-11-01T16:47:00+02:00").unwrap())
+    );
+}
+
+#[tokio::test]
+async fn imperial_values_are_picked() {
+    let Weather::Current(current) = forecast(FORECAST, ForecastTime::Now, UnitType::Imperial).await
+    else {
+        panic!("expected the current weather");
+    };
+
+    assert_eq!(current.temp, 46.4);
+    assert_eq!(current.wind_speed, 9.4);
+    assert_eq!(current.visibility, 5.0);
+    assert_eq!(current.precip, Some(0.01));
+}
+
+#[tokio::test]
+async fn hours_start_at_the_current_one() {
+    let Weather::Today(hours) = forecast(FORECAST, ForecastTime::Hours(3), UnitType::Metric).await
+    else {
+        panic!("expected an hourly forecast");
+    };
+
+    // Local time of the reply is 18:30, the 17:00 hour is over.
+    let times = hours
+        .iter()
+        .map(|h| h.time.to_rfc3339())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        times,
+        [
+            "2023-11-01T18:00:00+02:00",
+            "2023-11-01T19:00:00+02:00",
+            "2023-11-01T20:00:00+02:00",
+        ]
+    );
+    assert_eq!(hours[1].precip, Some(0.0));
+    assert_eq!(hours[1].condition, "Overcast");
+}
+
+#[tokio::test]
+async fn days_are_normalized() {
+    let Weather::Daily(days) = forecast(FORECAST, ForecastTime::Days(2), UnitType::Metric).await
+    else {
+        panic!("expected a daily forecast");
+    };
+
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2023, 11, 1).unwrap());
+    assert_eq!(days[0].min_temp, 6.9);
+    assert_eq!(days[0].max_temp, 11.2);
+    assert_eq!(days[0].precip, Some(4.1));
+    assert_eq!(days[0].moon_phase.as_deref(), Some("Waning Gibbous"));
+    assert_eq!(
+        days[0].moonset.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-11-01T12:21:00+02:00")
+    );
+    assert_eq!(days[1].condition, "Sunny");
+}
+
+#[tokio::test]
+async fn polar_night_has_no_sunrise() {
+    let Weather::Daily(days) =
+        forecast(FORECAST_POLAR, ForecastTime::Days(1), UnitType::Metric).await
+    else {
+        panic!("expected a daily forecast");
+    };
+
+    assert_eq!(days[0].sunrise, None);
+    assert_eq!(days[0].sunset, None);
+    assert_eq!(days[0].moonset, None);
+    assert_eq!(
+        days[0].moonrise.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-12-15T10:58:00+01:00")
+    );
+
+    let Weather::Current(current) =
+        forecast(FORECAST_POLAR, ForecastTime::Now, UnitType::Metric).await
+    else {
+        panic!("expected the current weather");
+    };
+    assert_eq!(current.sunrise, None);
+    assert_eq!(current.sunset, None);
+}
+
+#[tokio::test]
+async fn alerts_are_normalized() {
+    let server = StandIn::serve(200, FORECAST).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let alerts = provider.get_alerts(lviv()).await.unwrap();
+
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].severity.as_deref(), Some("Moderate"));
+    assert_eq!(
+        alerts[0].end.map(|t| t.to_rfc3339()).as_deref(),
+        Some("2023-11-02T08:00:00+02:00")
+    );
+}
+
+#[tokio::test]
+async fn hours_ask_for_enough_days() {
+    let server = StandIn::serve(200, FORECAST).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    // The fixture has two days, fewer than asked for, only the request matters here.
+    let _ = provider
+        .get_forecast(lviv(), ForecastTime::Hours(30), UnitType::Metric)
+        .await;
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("/v1/forecast.json?"));
+    assert!(requests[0].contains("q=49.84%2C24.03"));
+    // The first day is partly over, the remaining hours spill into the third one.
+    assert!(requests[0].contains("days=3"));
+}
+
+#[tokio::test]
+async fn days_beyond_the_plan_are_reported() {
+    let server = StandIn::serve(200, FORECAST).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    // The free plan answers with 3 days whatever was asked for, the fixture with 2.
+    let error = provider
+        .get_forecast(lviv(), ForecastTime::Days(5), UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::HorizonExceeded(_)), "{:?}", error);
+}
+
+#[tokio::test]
+async fn exhausted_quota_is_reported() {
+    let server = StandIn::serve(
+        403,
+        r#"{"error":{"code":2007,"message":"API key has exceeded calls per month quota."}}"#,
+    )
+    .await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::QuotaExceeded(_)));
+    assert_eq!(error.exit_code(), 5);
+}
+
+#[tokio::test]
+async fn air_quality_is_on_the_us_epa_scale() {
+    let server = StandIn::serve(200, FORECAST_AIR).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let AirForecast::Current(air) = provider
+        .get_air_quality(lviv(), ForecastTime::Now)
+        .await
+        .unwrap()
+    else {
+        panic!("expected the current air quality");
+    };
+
+    assert_eq!(air.aqi, 1);
+    assert_eq!(air.scale, AqiScale::UsEpa);
+    assert_eq!(air.co, 230.3);
+    assert_eq!(air.time.to_rfc3339(), "2023-11-01T18:30:00+02:00");
+    assert!(server.requests()[0].contains("aqi=yes"));
+}
+
+#[tokio::test]
+async fn air_quality_hours_start_at_the_current_one() {
+    let server = StandIn::serve(200, FORECAST_AIR).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let AirForecast::Hourly(hours) = provider
+        .get_air_quality(lviv(), ForecastTime::Hours(3))
+        .await
+        .unwrap()
+    else {
+        panic!("expected an hourly air quality forecast");
+    };
+
+    assert_eq!(
+        hours
+            .iter()
+            .map(|h| (h.time.to_rfc3339(), h.aqi))
+            .collect::<Vec<_>>(),
+        [
+            ("2023-11-01T18:00:00+02:00".to_string(), 1),
+            ("2023-11-01T19:00:00+02:00".to_string(), 2),
+            ("2023-11-01T20:00:00+02:00".to_string(), 2),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn missing_air_quality_is_unsupported() {
+    let server = StandIn::serve(200, FORECAST).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let error = provider
+        .get_air_quality(lviv(), ForecastTime::Now)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::Unsupported(_)));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn history_day_has_every_hour() {
+    let server = StandIn::serve(200, HISTORY).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let Weather::Today(hours) = provider
+        .get_history(lviv(), history((2023, 11, 1), None), UnitType::Metric)
+        .await
+        .unwrap()
+    else {
+        panic!("expected the hours of the day");
+    };
+
+    // Forecasts skip the hours before the local time, history keeps them.
+    assert_eq!(hours[0].time.to_rfc3339(), "2023-11-01T17:00:00+02:00");
+
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/v1/history.json?"));
+    assert!(requests[0].contains("dt=2023-11-01"));
+    assert!(!requests[0].contains("end_dt"));
+}
+
+#[tokio::test]
+async fn history_range_is_daily() {
+    let server = StandIn::serve(200, HISTORY).await;
+    let provider = WeatherApi::with_base_url("secret".to_string(), server.url.clone()).unwrap();
+
+    let Weather::Daily(days) = provider
+        .get_history(
+            lviv(),
+            history((2023, 11, 1), Some((2023, 11, 2))),
+            UnitType::Metric,
+        )
+        .await
+        .unwrap()
+    else {
+        panic!("expected a day per date");
+    };
+
+    assert_eq!(
+        days.iter().map(|d| d.date).collect::<Vec<_>>(),
+        [
+            NaiveDate::from_ymd_opt(2023, 11, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 11, 2).unwrap(),
+        ]
+    );
+    assert!(server.requests()[0].contains("end_dt=2023-11-02"));
+}
+
+#[test]
+fn history_ends_today() {
+    let date = |d| NaiveDate::from_ymd_opt(2023, 11, d).unwrap();
+
+    assert!(matches!(
+        HistoryRange::new(date(2), Some(date(1)), date(30)),
+        Err(AppError::DateRange(_))
+    ));
+    assert!(matches!(
+        HistoryRange::new(date(1), Some(date(3)), date(2)),
+        Err(AppError::DateRange(_))
+    ));
+    assert_eq!(
+        HistoryRange::new(date(1), Some(date(3)), date(3))
+            .unwrap()
+            .days(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn key_is_read_right_before_the_request() {
+    let server = StandIn::serve(200, FORECAST).await;
+    let key = ApiKey::lazy(ProviderCredentials {
+        key: None,
+        key_env: Some("WEATHER_TEST_UNSET_KEY".to_string()),
+        key_command: None,
+        key_file: None,
+    });
+    // Building the provider doesn't need the key.
+    let provider = WeatherApi::with_base_url(key, server.url.clone()).unwrap();
+
+    let error = provider
+        .get_forecast(lviv(), ForecastTime::Now, UnitType::Metric)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::Secret(_)));
+    assert!(error.is_provider_failure());
+    assert!(server.requests().is_empty());
+}